@@ -2,8 +2,11 @@
 //!
 //! Orchestrates all components and runs the main event loop.
 
+mod tasks;
+
 use std::io::{self, stdout};
 
+use chrono::Utc;
 use color_eyre::Result;
 use crossterm::{
     event::{self, Event, KeyEventKind},
@@ -11,13 +14,25 @@ use crossterm::{
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use ratatui::prelude::*;
-use tracing::info;
-
-use crate::config::Config;
-use crate::feed::{FeedItem, FeedManager};
-use crate::ui::{FeedListItem, UiState};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tracing::{info, warn};
+
+use crate::config::{Config, FolderConfig};
+use crate::download::{DownloadManager, DownloadState};
+use crate::feed::{FeedItem, FeedManager, FeedMoved, RefreshJob, RefreshProgress, Retention};
+use crate::http::ClientSettings;
+use crate::lock::{InstanceLock, Lock};
+use crate::ui::{FeedListItem, Panel, UiState};
 use ratatui_themes::Theme;
 
+pub use tasks::{AppEvent, SyncOutcome};
+
+/// A background refresh and whether it covers every feed.
+struct ActiveRefresh {
+    job: RefreshJob,
+    all_feeds: bool,
+}
+
 /// Main application state.
 pub struct App {
     /// Application configuration.
@@ -31,6 +46,21 @@ pub struct App {
 
     /// Theme configuration.
     pub theme: Theme,
+
+    /// Enclosure downloads.
+    pub downloads: DownloadManager,
+
+    /// Background refreshes in progress.
+    refresh_jobs: Vec<ActiveRefresh>,
+
+    /// Sender handed to background tasks.
+    events_tx: UnboundedSender<AppEvent>,
+
+    /// Results of background tasks.
+    events_rx: UnboundedReceiver<AppEvent>,
+
+    /// Lock on the data directory; `None` when running read-only.
+    _lock: Option<InstanceLock>,
 }
 
 impl App {
@@ -38,12 +68,29 @@ impl App {
     ///
     /// # Errors
     ///
-    /// Returns an error if configuration cannot be loaded or feeds cannot be initialized.
+    /// Returns an error if configuration cannot be loaded.
     pub async fn new() -> Result<Self> {
-        let config = Config::load()?;
+        // A second instance runs read-only rather than overwriting the first,
+        // so the lock comes before anything is loaded
+        let data_dir = Config::data_dir()
+            .ok_or_else(|| color_eyre::eyre::eyre!("Could not determine data directory"))?;
+        let (lock, other_pid) = match InstanceLock::acquire(&data_dir)? {
+            Lock::Acquired(lock) => (Some(lock), None),
+            Lock::Held(pid) => {
+                warn!("Another instance holds the lock; running read-only");
+                (None, pid)
+            }
+        };
+        let config = if lock.is_some() {
+            Config::load()?
+        } else {
+            Config::load_read_only()?
+        };
+
         let theme = config.theme;
         let sync_enabled = config.sync.is_some();
-        let feeds = FeedManager::new(&config)?;
+        let feeds = FeedManager::new(&config);
+        let downloads = DownloadManager::new(&config);
 
         // Check if we have cached data (offline mode)
         let has_cached = feeds.feeds.iter().any(|f| !f.items.is_empty());
@@ -57,21 +104,52 @@ impl App {
 
         let ui = UiState {
             sync_enabled,
+            read_only: config.read_only,
+            profile: Config::profile(),
             // Mark that we need to refresh feeds
             refreshing: !has_cached,
+            last_refresh: feeds.feeds.iter().filter_map(|f| f.last_updated).max(),
             ..Default::default()
         };
 
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+
         let mut app = Self {
             config,
             feeds,
             ui,
             theme,
+            downloads,
+            refresh_jobs: Vec::new(),
+            events_tx,
+            events_rx,
+            _lock: lock,
         };
 
+        if !app.config.issues.is_empty() {
+            let issues: Vec<String> = app.config.issues.iter().map(ToString::to_string).collect();
+            let file = Config::config_path()
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+                .unwrap_or_default();
+            app.ui
+                .show_error_dialog(format!("Problems in {file}"), Some(issues.join("\n")));
+        }
+
+        if app.config.read_only {
+            let who = other_pid.map_or_else(String::new, |pid| format!(" (pid {pid})"));
+            app.ui.show_error_dialog(
+                "feedo is already running",
+                Some(format!(
+                    "Another feedo{who} is using your data.\n\nThis window is read-only: \
+                     read states, new feeds and settings changed here won't be saved."
+                )),
+            );
+        }
+
         // Build initial feed list
         app.rebuild_feed_list();
         app.select_first_feed();
+        app.schedule_next_refresh();
 
         Ok(app)
     }
@@ -109,11 +187,29 @@ impl App {
         use crossterm::event::poll;
         use std::time::Duration;
 
-        // Track if we need initial refresh
-        let mut needs_initial_refresh = self.ui.refreshing;
-        let mut update_check_done = false;
+        // Initial refresh of feeds that have never been fetched
+        if self.ui.refreshing {
+            let never_fetched: Vec<usize> = self
+                .feeds
+                .feeds
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| f.last_updated.is_none())
+                .map(|(i, _)| i)
+                .collect();
+            self.start_refresh(never_fetched);
+        }
+
+        // Check for updates in background (once)
+        tasks::spawn_update_check(self.events_tx.clone(), ClientSettings::global(&self.config));
 
         loop {
+            // Merge finished background work before drawing
+            self.poll_refresh();
+            self.poll_events();
+            self.poll_downloads();
+            self.maybe_auto_refresh();
+
             // Render
             terminal.draw(|frame| self.render(frame))?;
 
@@ -124,49 +220,199 @@ impl App {
                 terminal.draw(|frame| self.render(frame))?;
             }
 
-            // Use poll with timeout to allow background work
+            // Use poll with timeout so background results get drawn promptly
             if poll(Duration::from_millis(100))? {
                 if let Event::Key(key) = event::read()? {
                     if key.kind == KeyEventKind::Press {
-                        match self.handle_key(key.code).await {
+                        match self.handle_key(key.code) {
                             crate::ui::input::KeyResult::Quit => break,
                             crate::ui::input::KeyResult::Continue => {}
                         }
                     }
                 }
-            } else {
-                // No input - do background work
-
-                // Initial refresh (one feed at a time to stay responsive)
-                if needs_initial_refresh {
-                    if let Some(idx) = self
-                        .feeds
-                        .feeds
-                        .iter()
-                        .position(|f| f.last_updated.is_none())
-                    {
-                        self.feeds.refresh_feed(idx).await;
-                        self.rebuild_feed_list();
-                    } else {
-                        needs_initial_refresh = false;
-                        self.ui.refreshing = false;
-                        self.feeds.save_cache();
-                    }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Start refreshing the given feeds in the background.
+    pub fn start_refresh(&mut self, indices: impl IntoIterator<Item = usize>) {
+        self.spawn_refresh(indices, false);
+    }
+
+    /// Start refreshing all feeds in the background.
+    ///
+    /// Does nothing if a full refresh is already running.
+    pub fn start_refresh_all(&mut self) {
+        if self.refresh_jobs.iter().any(|r| r.all_feeds) {
+            return;
+        }
+        self.spawn_refresh(0..self.feeds.feeds.len(), true);
+    }
+
+    /// Queue a refresh job for the given feeds.
+    ///
+    /// Feeds that a running refresh is already fetching are left to it.
+    fn spawn_refresh(&mut self, indices: impl IntoIterator<Item = usize>, all_feeds: bool) {
+        let indices: Vec<usize> = indices
+            .into_iter()
+            .filter(|&i| {
+                self.feeds
+                    .feeds
+                    .get(i)
+                    .is_some_and(|f| !self.refresh_jobs.iter().any(|r| r.job.is_fetching(&f.url)))
+            })
+            .collect();
+
+        if !indices.is_empty() {
+            let job = self.feeds.start_refresh(indices);
+            self.refresh_jobs.push(ActiveRefresh { job, all_feeds });
+        }
+        self.update_refresh_progress();
+    }
+
+    /// Whether a background refresh is running.
+    #[must_use]
+    pub fn is_refreshing(&self) -> bool {
+        !self.refresh_jobs.is_empty()
+    }
+
+    /// Merge finished fetches from background refreshes.
+    fn poll_refresh(&mut self) {
+        if self.refresh_jobs.is_empty() {
+            return;
+        }
+
+        let mut changed = false;
+        let mut moved = Vec::new();
+        for active in &mut self.refresh_jobs {
+            while let Some(outcome) = active.job.try_next() {
+                moved.extend(self.feeds.apply_outcome(outcome));
+                changed = true;
+            }
+        }
+        self.apply_feed_moves(&moved);
+
+        let jobs = self.refresh_jobs.len();
+        self.refresh_jobs.retain(|r| !r.job.is_finished());
+
+        if self.refresh_jobs.len() < jobs {
+            self.ui.last_refresh = Some(Utc::now());
+            self.schedule_next_refresh();
+        }
+
+        self.update_refresh_progress();
+
+        if self.refresh_jobs.is_empty() {
+            self.feeds.save_cache();
+        }
+
+        if changed {
+            self.rebuild_feed_list();
+        }
+    }
+
+    /// Point the config at feeds' new URLs after permanent redirects.
+    fn apply_feed_moves(&mut self, moved: &[FeedMoved]) {
+        if moved.is_empty() {
+            return;
+        }
+
+        for m in moved {
+            self.config.rename_feed_url(&m.from, &m.to);
+        }
+        // Credentials only follow once the new URLs are on disk
+        match self.config.save() {
+            Ok(()) => {
+                for m in moved {
+                    self.config.move_credentials(&m.from, &m.to);
                 }
+            }
+            Err(e) => warn!("Failed to save config after feed moved: {e}"),
+        }
+
+        let message = match moved {
+            [m] => format!("Feed moved: {} → {}", m.name, m.to),
+            _ => format!("{} feeds moved to new URLs", moved.len()),
+        };
+        self.ui.set_status(message);
+    }
+
+    /// Combine the progress of all running refreshes for the status bar.
+    fn update_refresh_progress(&mut self) {
+        self.ui.refreshing = self.is_refreshing();
+        self.ui.refresh_progress = self.is_refreshing().then(|| {
+            self.refresh_jobs.iter().map(|r| r.job.progress()).fold(
+                RefreshProgress::default(),
+                |acc, p| RefreshProgress {
+                    done: acc.done + p.done,
+                    total: acc.total + p.total,
+                },
+            )
+        });
+    }
 
-                // Check for updates in background (once)
-                if !update_check_done && !needs_initial_refresh {
-                    update_check_done = true;
-                    if let crate::VersionCheck::UpdateAvailable { latest, .. } =
-                        crate::check_for_updates_crates_io().await
-                    {
+    /// Handle results from background tasks.
+    fn poll_events(&mut self) {
+        while let Ok(event) = self.events_rx.try_recv() {
+            match event {
+                AppEvent::Discovered { url, result } => self.finish_discovery(&url, result),
+                AppEvent::SyncFinished(result) => self.finish_sync(result),
+                AppEvent::UpdateChecked(check) => {
+                    if let crate::VersionCheck::UpdateAvailable { latest, .. } = check {
                         self.ui.update_available = Some(latest);
                     }
                 }
             }
         }
+    }
 
-        Ok(())
+    /// Report downloads that finished since the last tick.
+    fn poll_downloads(&mut self) {
+        for index in self.downloads.poll() {
+            let download = &self.downloads.downloads()[index];
+            match &download.state {
+                DownloadState::Done => {
+                    self.ui
+                        .set_status(format!("✓ Downloaded {}", download.title));
+                }
+                DownloadState::Failed(e) => {
+                    self.ui
+                        .set_error(format!("Download of {} failed: {e}", download.title));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Find when the next feed is due for an automatic refresh.
+    fn schedule_next_refresh(&mut self) {
+        self.ui.next_refresh = self.feeds.next_refresh_at(Utc::now());
+    }
+
+    /// Refresh the feeds whose schedule says they are due.
+    ///
+    /// Waits for running refreshes to finish first, so a feed is never
+    /// fetched twice at once.
+    fn maybe_auto_refresh(&mut self) {
+        if self.is_refreshing() {
+            return;
+        }
+
+        let now = Utc::now();
+        if self.ui.next_refresh.is_none_or(|due| now < due) {
+            return;
+        }
+
+        let due = self.feeds.due_feeds(now);
+        if due.is_empty() {
+            // Feeds changed since the schedule was computed
+            self.schedule_next_refresh();
+        } else {
+            info!("Refreshing {} feeds due by schedule", due.len());
+            self.start_refresh(due);
+        }
     }
 
     /// Process a pending update.
@@ -232,6 +478,33 @@ impl App {
         }
     }
 
+    /// Select a feed in the list, expanding its folder if needed.
+    pub fn select_feed(&mut self, feed_idx: usize) {
+        if let Some(folder) = self
+            .feeds
+            .folders
+            .iter_mut()
+            .find(|f| f.feed_indices.contains(&feed_idx))
+        {
+            folder.expanded = true;
+        }
+        self.rebuild_feed_list();
+
+        if let Some(pos) = self
+            .ui
+            .feed_list
+            .iter()
+            .position(|&item| item == FeedListItem::Feed(feed_idx))
+        {
+            self.ui.feed_list_index = pos;
+            self.ui.selected_feed = Some(feed_idx);
+            self.ui.selected_item = 0;
+            self.ui.panel = Panel::Feeds;
+            self.sync_feed_list_state();
+            self.sync_items_list_state();
+        }
+    }
+
     /// Get items from the currently selected feed.
     #[must_use]
     pub fn current_feed_items(&self) -> &[FeedItem] {
@@ -250,14 +523,36 @@ impl App {
             .and_then(|f| f.items.get(self.ui.selected_item))
     }
 
-    /// Run sync with configured server.
+    /// The selected item's text from before its last change, if it changed.
+    #[must_use]
+    pub fn previous_body(&self) -> Option<&str> {
+        let item = self.selected_item()?;
+        item.previous_body.as_deref().or_else(|| {
+            self.ui
+                .seen_change
+                .as_ref()
+                .filter(|(id, _)| *id == item.id)
+                .map(|(_, body)| body.as_str())
+        })
+    }
+
+    /// Start discovering feeds at `url` in the background.
+    ///
+    /// The result is delivered to [`App::finish_discovery`].
+    pub fn spawn_discovery(&self, url: String) {
+        tasks::spawn_discovery(
+            self.events_tx.clone(),
+            url,
+            ClientSettings::global(&self.config),
+        );
+    }
+
+    /// Start a sync with the configured server in the background.
     ///
     /// # Errors
     ///
-    /// Returns an error if sync is not configured or the sync operation fails.
-    pub async fn run_sync(&mut self) -> Result<()> {
-        use crate::sync::SyncManager;
-
+    /// Returns an error if sync is not configured or no credentials are stored.
+    pub fn start_sync(&mut self) -> Result<()> {
         let sync = self
             .config
             .sync
@@ -268,29 +563,90 @@ impl App {
             .get_credentials()
             .ok_or_else(|| color_eyre::eyre::eyre!("No credentials stored"))?;
 
-        let manager = SyncManager::connect(&sync.server, &username, &password).await?;
+        // Persist local read states so the server sees them
+        self.feeds.save_cache();
+
+        tasks::spawn_sync(
+            self.events_tx.clone(),
+            sync,
+            username,
+            password,
+            self.config.clone(),
+            self.feeds.cache.snapshot(),
+        );
+
+        self.ui.syncing = true;
+        self.ui.set_status("⟳ Syncing...");
+        Ok(())
+    }
+
+    /// Merge the result of a background sync.
+    fn finish_sync(&mut self, outcome: Result<Box<SyncOutcome>>) {
+        self.ui.syncing = false;
 
-        let result = manager
-            .full_sync(&mut self.config, &mut self.feeds.cache)
-            .await?;
+        let SyncOutcome {
+            imported,
+            sync_ids,
+            result,
+        } = match outcome {
+            Ok(outcome) => *outcome,
+            Err(e) => {
+                self.ui.set_error(format!("Sync failed: {e}"));
+                return;
+            }
+        };
+
+        // Feeds may have moved or been added while the sync ran, so only its
+        // additions are merged into the config as it is now
+        for (url, id) in sync_ids {
+            if let Some(feed) = self.config.feed_mut(&url) {
+                feed.sync_id.get_or_insert(id);
+            }
+        }
+        for (folder, feed) in imported {
+            if self.config.has_feed(&feed.url) {
+                continue;
+            }
+            let Some(name) = folder else {
+                self.config.feeds.push(feed);
+                continue;
+            };
+            match self
+                .config
+                .folders
+                .iter_mut()
+                .find(|f| f.name.eq_ignore_ascii_case(&name))
+            {
+                Some(folder) => folder.feeds.push(feed),
+                None => self.config.folders.push(FolderConfig {
+                    name,
+                    icon: None,
+                    expanded: true,
+                    retention: Retention::default(),
+                    feeds: vec![feed],
+                }),
+            }
+        }
+        result.apply_read_states(&mut self.feeds.cache);
+        self.feeds.reload_read_states();
 
         // Save changes
-        self.config.save()?;
+        if let Err(e) = self.config.save() {
+            self.ui.set_error(format!("Failed to save config: {e}"));
+        }
         self.feeds.save_cache();
 
-        // Reload feeds if new subscriptions were imported
         if result.feeds_imported > 0 {
-            self.feeds = crate::feed::FeedManager::new(&self.config)?;
-            self.feeds.refresh_all().await;
-            self.rebuild_feed_list();
+            // Reload feeds so the new subscriptions show up, then fetch them
+            self.feeds.reload(&self.config);
+            self.start_refresh_all();
         }
+        self.rebuild_feed_list();
 
         self.ui.set_status(format!(
             "✓ Sync complete: +{} feeds, {} read",
             result.feeds_imported,
             result.items_marked_read + result.items_synced_to_server
         ));
-
-        Ok(())
     }
 }
@@ -1,16 +1,32 @@
 //! Configuration data structures.
 
-use std::{env, fs, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
 
 use color_eyre::Result;
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
+use super::ConfigFormat;
+use super::schema::{self, CONFIG_VERSION, ConfigIssue};
+use crate::feed::{RefreshLimits, Retention};
 use crate::sync::SyncConfig;
 use ratatui_themes::Theme;
 
+/// Profile chosen on the command line, overriding `FEEDO_PROFILE`.
+static PROFILE: OnceLock<String> = OnceLock::new();
+
 /// Application configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Version of the config format, see [`CONFIG_VERSION`].
+    #[serde(default)]
+    pub version: u32,
+
     /// Folders containing feeds.
     #[serde(default)]
     pub folders: Vec<FolderConfig>,
@@ -27,9 +43,63 @@ pub struct Config {
     #[serde(default = "default_refresh_interval")]
     pub refresh_interval: u32,
 
+    /// Maximum number of feeds fetched at the same time.
+    #[serde(default = "default_max_concurrent_fetches")]
+    pub max_concurrent_fetches: usize,
+
+    /// Maximum number of simultaneous requests to a single host.
+    #[serde(default = "default_max_fetches_per_host")]
+    pub max_fetches_per_host: usize,
+
+    /// Days a feed may keep failing before it is suspended (0 = never).
+    #[serde(default = "default_dead_feed_days")]
+    pub dead_feed_days: u32,
+
+    /// How long articles that dropped out of their feed are kept.
+    #[serde(default, skip_serializing_if = "Retention::is_default")]
+    pub retention: Retention,
+
+    /// Mark articles unread again when their text changes after they were read.
+    #[serde(default)]
+    pub mark_updated_unread: bool,
+
+    /// Proxy for all requests: `http://`, `https://`, `socks5://` or `socks5h://` URL.
+    ///
+    /// When unset, the `HTTP_PROXY`/`HTTPS_PROXY` environment variables apply.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+
+    /// Hosts that bypass [`Config::proxy`], e.g. `localhost` or `.internal.example.com`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_proxy: Vec<String>,
+
+    /// PEM files with extra CA certificates to trust, e.g. a private CA.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ca_certificates: Vec<PathBuf>,
+
+    /// SHA-256 certificate fingerprints by host, for self-signed certificates.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pinned_certificates: BTreeMap<String, String>,
+
+    /// Where enclosures are downloaded to (default: `~/Downloads/feedo`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_dir: Option<PathBuf>,
+
+    /// Command used to play enclosures; the file or URL is appended.
+    #[serde(default = "default_player")]
+    pub player: String,
+
     /// Sync configuration (optional).
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub sync: Option<SyncConfig>,
+
+    /// Whether another instance owns the data directory, so nothing is saved.
+    #[serde(skip)]
+    pub read_only: bool,
+
+    /// Problems found in the config file that didn't stop it loading.
+    #[serde(skip)]
+    pub issues: Vec<ConfigIssue>,
 }
 
 /// A folder containing multiple feeds.
@@ -46,12 +116,16 @@ pub struct FolderConfig {
     #[serde(default = "default_true")]
     pub expanded: bool,
 
+    /// Retention settings for the folder's feeds, overriding the global ones.
+    #[serde(default, skip_serializing_if = "Retention::is_default")]
+    pub retention: Retention,
+
     /// Feeds in this folder.
     pub feeds: Vec<FeedConfig>,
 }
 
 /// A single feed configuration.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FeedConfig {
     /// Display name.
     pub name: String,
@@ -62,6 +136,108 @@ pub struct FeedConfig {
     /// Sync ID from server (e.g., "feed/123" for Google Reader API).
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub sync_id: Option<String>,
+
+    /// Fixed refresh interval in minutes, overriding the adaptive schedule.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_interval: Option<u32>,
+
+    /// Retention settings, overriding the folder's and the global ones.
+    #[serde(default, skip_serializing_if = "Retention::is_default")]
+    pub retention: Retention,
+
+    /// HTTP Basic auth username. The credentials live in encrypted storage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+
+    /// HTTP Basic auth password (fallback if encrypted storage unavailable).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+
+    /// Extra HTTP headers sent with each request, e.g. an API token.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+
+    /// User-Agent override.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+
+    /// Request timeout in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+
+    /// Proxy URL for this feed, overriding the global one. `"direct"` bypasses any proxy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+}
+
+impl FeedConfig {
+    /// Key under which a feed's credentials are stored.
+    #[must_use]
+    pub fn credential_key(url: &str) -> String {
+        format!("feed@{url}")
+    }
+
+    /// Get Basic auth credentials from encrypted storage or config fallback.
+    #[must_use]
+    pub fn get_credentials(&self) -> Option<(String, String)> {
+        let username = self.username.as_ref()?;
+        if let Some(creds) = crate::credentials::get_credentials(&Self::credential_key(&self.url)) {
+            return Some(creds);
+        }
+        self.password.clone().map(|p| (username.clone(), p))
+    }
+
+    /// Store Basic auth credentials, encrypted if possible.
+    ///
+    /// Returns `false` if encryption failed and the password was kept in the
+    /// config file instead.
+    pub fn set_credentials(&mut self, username: &str, password: &str) -> bool {
+        let key = Self::credential_key(&self.url);
+        let encrypted = crate::credentials::store_credentials(&key, username, password).is_ok();
+
+        self.username = Some(username.to_string());
+        self.password = (!encrypted).then(|| password.to_string());
+        encrypted
+    }
+}
+
+/// Whether `name` can be used as a profile (and directory) name.
+fn is_valid_profile(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+/// `$var/feedo`, if the variable holds an absolute path as the XDG spec requires.
+fn xdg_dir(var: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .map(|p| p.join("feedo"))
+}
+
+/// The XDG location, unless only the pre-XDG one has data in it.
+fn prefer_existing(xdg: Option<PathBuf>, legacy: Option<PathBuf>) -> Option<PathBuf> {
+    match (xdg, legacy) {
+        (Some(xdg), Some(legacy)) if !xdg.exists() && legacy.exists() => Some(legacy),
+        (Some(xdg), _) => Some(xdg),
+        (None, legacy) => legacy,
+    }
+}
+
+/// `base`, or the active profile's directory below it.
+fn with_profile(base: PathBuf) -> PathBuf {
+    match Config::profile() {
+        Some(name) => base.join("profiles").join(name),
+        None => base,
+    }
 }
 
 const fn default_true() -> bool {
@@ -72,24 +248,44 @@ const fn default_refresh_interval() -> u32 {
     30 // 30 minutes
 }
 
+const fn default_dead_feed_days() -> u32 {
+    7
+}
+
+fn default_player() -> String {
+    "mpv".to_string()
+}
+
+fn default_max_concurrent_fetches() -> usize {
+    RefreshLimits::default().max_concurrent
+}
+
+fn default_max_fetches_per_host() -> usize {
+    RefreshLimits::default().max_per_host
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
+            version: CONFIG_VERSION,
             folders: vec![
                 FolderConfig {
                     name: "Tech".to_string(),
                     icon: Some("💻".to_string()),
                     expanded: true,
+                    retention: Retention::default(),
                     feeds: vec![
                         FeedConfig {
                             name: "Hacker News".to_string(),
                             url: "https://hnrss.org/frontpage".to_string(),
                             sync_id: None,
+                            ..Default::default()
                         },
                         FeedConfig {
                             name: "Lobsters".to_string(),
                             url: "https://lobste.rs/rss".to_string(),
                             sync_id: None,
+                            ..Default::default()
                         },
                     ],
                 },
@@ -97,66 +293,130 @@ impl Default for Config {
                     name: "News".to_string(),
                     icon: Some("📰".to_string()),
                     expanded: false,
+                    retention: Retention::default(),
                     feeds: vec![FeedConfig {
                         name: "BBC World".to_string(),
                         url: "https://feeds.bbci.co.uk/news/world/rss.xml".to_string(),
                         sync_id: None,
+                        ..Default::default()
                     }],
                 },
             ],
             feeds: vec![],
             theme: Theme::default(),
             refresh_interval: default_refresh_interval(),
+            max_concurrent_fetches: default_max_concurrent_fetches(),
+            max_fetches_per_host: default_max_fetches_per_host(),
+            dead_feed_days: default_dead_feed_days(),
+            retention: Retention::default(),
+            mark_updated_unread: false,
+            proxy: None,
+            no_proxy: Vec::new(),
+            ca_certificates: Vec::new(),
+            pinned_certificates: BTreeMap::new(),
+            download_dir: None,
+            player: default_player(),
             sync: None,
+            read_only: false,
+            issues: Vec::new(),
         }
     }
 }
 
 impl Config {
-    /// Get the configuration directory path.
+    /// Select the profile for this process, e.g. from `--profile`.
     ///
-    /// Uses `~/.config/feedo` on all platforms for consistency.
-    /// Falls back to platform-specific directories if HOME is not set.
-    #[must_use]
-    pub fn config_dir() -> Option<PathBuf> {
-        // Prefer ~/.config/feedo on all platforms (XDG-style)
-        if let Ok(home) = env::var("HOME") {
-            return Some(PathBuf::from(home).join(".config").join("feedo"));
+    /// Takes precedence over `FEEDO_PROFILE`. Has no effect once set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name isn't a valid profile name.
+    pub fn set_profile(name: &str) -> Result<()> {
+        if !is_valid_profile(name) {
+            return Err(color_eyre::eyre::eyre!(
+                "Invalid profile name '{name}': use letters, digits, '-' and '_'"
+            ));
         }
+        let _ = PROFILE.set(name.to_string());
+        Ok(())
+    }
 
-        // Fallback for Windows if HOME not set
-        if let Ok(userprofile) = env::var("USERPROFILE") {
-            return Some(PathBuf::from(userprofile).join(".config").join("feedo"));
-        }
+    /// The active profile, if any: from [`Self::set_profile`], else `FEEDO_PROFILE`.
+    ///
+    /// The `feedo` binary passes `FEEDO_PROFILE` through [`Self::set_profile`]
+    /// so a bad name is an error; here, invalid names are ignored.
+    #[must_use]
+    pub fn profile() -> Option<String> {
+        PROFILE
+            .get()
+            .cloned()
+            .or_else(|| env::var("FEEDO_PROFILE").ok())
+            .filter(|name| is_valid_profile(name))
+    }
 
-        None
+    /// Get the configuration directory path.
+    ///
+    /// `$XDG_CONFIG_HOME/feedo` if set, else `~/.config/feedo` on all
+    /// platforms. A profile lives in `profiles/<name>` below that, with its
+    /// own config, credentials and (unless `XDG_DATA_HOME` is set) data.
+    #[must_use]
+    pub fn config_dir() -> Option<PathBuf> {
+        let legacy = home_dir().map(|home| home.join(".config").join("feedo"));
+        let base = prefer_existing(xdg_dir("XDG_CONFIG_HOME"), legacy)?;
+        Some(with_profile(base))
     }
 
     /// Get the configuration file path.
+    ///
+    /// `config.toml` if there is one, otherwise `config.json`.
     #[must_use]
     pub fn config_path() -> Option<PathBuf> {
-        Self::config_dir().map(|dir| dir.join("config.json"))
+        let dir = Self::config_dir()?;
+        let toml = dir.join(ConfigFormat::Toml.file_name());
+        if toml.exists() {
+            Some(toml)
+        } else {
+            Some(dir.join(ConfigFormat::Json.file_name()))
+        }
     }
 
     /// Get the data directory path (for caches, read states, etc.).
+    ///
+    /// `$XDG_DATA_HOME/feedo` if set, else `data` in the config directory.
     #[must_use]
     pub fn data_dir() -> Option<PathBuf> {
-        Self::config_dir().map(|dir| dir.join("data"))
+        let legacy = Self::config_dir().map(|dir| dir.join("data"));
+        match xdg_dir("XDG_DATA_HOME") {
+            Some(base) => prefer_existing(Some(with_profile(base)), legacy),
+            None => legacy,
+        }
+    }
+
+    /// Directory enclosures are downloaded to.
+    #[must_use]
+    pub fn download_dir(&self) -> Option<PathBuf> {
+        self.download_dir.clone().or_else(|| {
+            directories::UserDirs::new()
+                .and_then(|dirs| dirs.download_dir().map(|d| d.join("feedo")))
+                .or_else(|| Self::data_dir().map(|d| d.join("downloads")))
+        })
     }
 
     /// Load configuration from disk, creating default if not exists.
     ///
+    /// Files from older versions are migrated. Problems that don't stop the
+    /// config loading end up in [`Self::issues`].
+    ///
     /// # Errors
     ///
-    /// Returns an error if the configuration file cannot be read or parsed.
+    /// Returns an error if the configuration file cannot be read or parsed,
+    /// naming the line and setting at fault.
     pub fn load() -> Result<Self> {
         let path = Self::config_path()
             .ok_or_else(|| color_eyre::eyre::eyre!("Could not determine config directory"))?;
 
         if path.exists() {
-            let content = fs::read_to_string(&path)?;
-            let config: Self = serde_json::from_str(&content)?;
-            Ok(config)
+            Self::read(&path, true)
         } else {
             let config = Self::default();
             config.save()?;
@@ -164,22 +424,197 @@ impl Config {
         }
     }
 
-    /// Save configuration to disk.
+    /// Load configuration without writing anything, for when another
+    /// instance owns it.
+    ///
+    /// A missing file gives the defaults, and a corrupt one isn't restored
+    /// from a backup. The config is [read-only](Self::read_only).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration file cannot be read or parsed,
+    /// naming the line and setting at fault.
+    pub fn load_read_only() -> Result<Self> {
+        let path = Self::config_path()
+            .ok_or_else(|| color_eyre::eyre::eyre!("Could not determine config directory"))?;
+
+        let mut config = if path.exists() {
+            Self::read(&path, false)?
+        } else {
+            Self::default()
+        };
+        config.read_only = true;
+        Ok(config)
+    }
+
+    /// Read and migrate the config file at `path`, restoring it from a
+    /// backup if it is corrupt and `restore` is set.
+    fn read(path: &Path, restore: bool) -> Result<Self> {
+        let format = ConfigFormat::of(path);
+        let ignored = path.with_file_name(format.other().file_name());
+        if format == ConfigFormat::Toml && ignored.exists() {
+            warn!(
+                "Using {} and ignoring {}",
+                path.display(),
+                ignored.display()
+            );
+        }
+
+        let load = |path: &Path| -> Result<(String, serde_json::Value)> {
+            let content = fs::read_to_string(path)?;
+            if content.is_empty() || content.contains('\0') {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "file is empty or truncated",
+                )
+                .into());
+            }
+            let value = format
+                .parse(&content)
+                .map_err(|e| color_eyre::eyre::eyre!("Invalid config {}: {e}", path.display()))?;
+            Ok((content, value))
+        };
+        // Only a file that was cut short or isn't text is replaced by a
+        // backup; a mistake made while editing it is for the user to fix
+        let (content, value) = if restore {
+            crate::persist::load_or_restore(path, load, |e| {
+                e.downcast_ref::<io::Error>()
+                    .is_some_and(|e| e.kind() == io::ErrorKind::InvalidData)
+            })?
+        } else {
+            load(path)?
+        };
+
+        let config = schema::load(&content, format, value).map_err(|issue| {
+            color_eyre::eyre::eyre!("Invalid config {}: {issue}", path.display())
+        })?;
+        for issue in &config.issues {
+            warn!("{}: {issue}", path.display());
+        }
+        Ok(config)
+    }
+
+    /// Save configuration to disk, in the format of the existing file.
+    ///
+    /// The file is replaced atomically, and the previous version is kept as
+    /// a backup. Comments in a TOML file are kept. Does nothing in
+    /// [read-only](Self::read_only) mode.
     ///
     /// # Errors
     ///
     /// Returns an error if the configuration file cannot be written.
     pub fn save(&self) -> Result<()> {
-        let dir = Self::config_dir()
+        if self.read_only {
+            return Ok(());
+        }
+        let path = Self::config_path()
             .ok_or_else(|| color_eyre::eyre::eyre!("Could not determine config directory"))?;
-        fs::create_dir_all(&dir)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
 
-        let path = dir.join("config.json");
-        let content = serde_json::to_string_pretty(self)?;
-        fs::write(path, content)?;
+        let old = fs::read_to_string(&path).ok();
+        let content = ConfigFormat::of(&path).write(self, old.as_deref())?;
+        match old {
+            Some(old) if old == content => return Ok(()),
+            Some(_) => {
+                fs::copy(&path, crate::persist::next_backup(&path)?)?;
+            }
+            None => {}
+        }
+        crate::persist::write_atomic(&path, content.as_bytes())?;
         Ok(())
     }
 
+    /// Rewrite the config file in another format.
+    ///
+    /// The old file is moved to its newest backup, so only the new one is
+    /// read from then on. Returns the paths of the new and old files.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the config is already in that format, or a file
+    /// cannot be written or moved.
+    pub fn convert(&self, to: ConfigFormat) -> Result<(PathBuf, PathBuf)> {
+        if self.read_only {
+            return Err(color_eyre::eyre::eyre!(
+                "Another feedo is running; quit it before converting the config"
+            ));
+        }
+        let from = Self::config_path()
+            .ok_or_else(|| color_eyre::eyre::eyre!("Could not determine config directory"))?;
+        if ConfigFormat::of(&from) == to {
+            return Err(color_eyre::eyre::eyre!(
+                "{} is already in {to} format",
+                from.display()
+            ));
+        }
+
+        let path = from.with_file_name(to.file_name());
+        crate::persist::write_atomic(&path, to.write(self, None)?.as_bytes())?;
+        let backup = crate::persist::next_backup(&from)?;
+        fs::rename(&from, &backup)?;
+        Ok((path, backup))
+    }
+
+    /// Find a feed by URL, in a folder or at the root.
+    pub fn feed_mut(&mut self, url: &str) -> Option<&mut FeedConfig> {
+        self.folders
+            .iter_mut()
+            .flat_map(|f| f.feeds.iter_mut())
+            .chain(self.feeds.iter_mut())
+            .find(|f| f.url == url)
+    }
+
+    /// Change the URL of a feed, wherever it lives.
+    ///
+    /// Stored credentials stay where they are; call
+    /// [`Self::move_credentials`] once the config has been saved.
+    /// Returns `false` if no feed has the old URL.
+    pub fn rename_feed_url(&mut self, from: &str, to: &str) -> bool {
+        let Some(feed) = self.feed_mut(from) else {
+            return false;
+        };
+        feed.url = to.to_string();
+        true
+    }
+
+    /// Move the stored credentials of a feed renamed from `from` to `to`.
+    ///
+    /// Credentials are stored by URL, so they follow a saved rename. Does
+    /// nothing in [read-only](Self::read_only) mode, where the config on
+    /// disk still has the old URL.
+    pub fn move_credentials(&self, from: &str, to: &str) {
+        if self.read_only
+            || !self
+                .folders
+                .iter()
+                .flat_map(|f| &f.feeds)
+                .chain(&self.feeds)
+                .any(|f| f.url == to && f.username.is_some())
+        {
+            return;
+        }
+
+        let from_key = FeedConfig::credential_key(from);
+        if let Some((username, password)) = crate::credentials::get_credentials(&from_key) {
+            let to_key = FeedConfig::credential_key(to);
+            if crate::credentials::store_credentials(&to_key, &username, &password).is_ok() {
+                let _ = crate::credentials::delete_credentials(&from_key);
+            }
+        }
+    }
+
+    /// Whether a feed with this URL is subscribed, in a folder or at the root.
+    #[must_use]
+    pub fn has_feed(&self, url: &str) -> bool {
+        self.folders
+            .iter()
+            .flat_map(|f| &f.feeds)
+            .chain(&self.feeds)
+            .any(|f| f.url == url)
+    }
+
     /// Count total number of feeds across all folders and root.
     #[must_use]
     pub fn total_feeds(&self) -> usize {
@@ -199,6 +634,63 @@ mod tests {
         assert_eq!(config.refresh_interval, 30);
     }
 
+    #[test]
+    fn test_profile_dirs() {
+        assert!(is_valid_profile("work"));
+        assert!(is_valid_profile("side-project_2"));
+        assert!(!is_valid_profile(""));
+        assert!(!is_valid_profile("../etc"));
+        assert!(!is_valid_profile("a b"));
+
+        // XDG wins, unless only the old location exists
+        let existing = std::env::temp_dir();
+        let missing = existing.join("feedo-no-such-dir");
+        assert_eq!(
+            prefer_existing(Some(missing.clone()), Some(existing.clone())),
+            Some(existing.clone())
+        );
+        assert_eq!(
+            prefer_existing(Some(existing.clone()), Some(missing.clone())),
+            Some(existing)
+        );
+        assert_eq!(prefer_existing(None, Some(missing.clone())), Some(missing));
+    }
+
+    #[test]
+    fn test_rename_feed_url() {
+        let mut config = Config::default();
+        assert!(config.rename_feed_url("https://lobste.rs/rss", "https://lobste.rs/rss.xml"));
+        assert_eq!(config.folders[0].feeds[1].url, "https://lobste.rs/rss.xml");
+        assert!(!config.rename_feed_url("https://lobste.rs/rss", "https://example.com"));
+    }
+
+    #[test]
+    fn test_read_only_rename_keeps_credentials() {
+        let from = "https://example.com/feedo-test-moved.xml";
+        let to = "https://example.com/feedo-test-moved-to.xml";
+        let mut config = Config {
+            read_only: true,
+            ..Config::default()
+        };
+        config.feeds.push(FeedConfig {
+            name: "Moved".to_string(),
+            url: from.to_string(),
+            username: Some("me".to_string()),
+            ..Default::default()
+        });
+        let key = FeedConfig::credential_key(from);
+        crate::credentials::store_credentials(&key, "me", "secret").unwrap();
+        let path = Config::config_dir().unwrap().join(".credentials");
+        let before = fs::read(&path).unwrap();
+
+        assert!(config.rename_feed_url(from, to));
+        config.move_credentials(from, to);
+        assert_eq!(fs::read(&path).unwrap(), before);
+        assert!(crate::credentials::get_credentials(&FeedConfig::credential_key(to)).is_none());
+
+        let _ = crate::credentials::delete_credentials(&key);
+    }
+
     #[test]
     fn test_config_serialization() {
         let config = Config::default();
@@ -246,5 +738,8 @@ mod tests {
 
         let config: Config = serde_json::from_str(json).unwrap();
         assert_eq!(config.theme.name, ThemeName::Dracula);
+        assert_eq!(config.max_concurrent_fetches, 8);
+        assert_eq!(config.max_fetches_per_host, 2);
+        assert_eq!(config.dead_feed_days, 7);
     }
 }
@@ -0,0 +1,455 @@
+//! Config file formats.
+//!
+//! The config can be written as `config.json` or `config.toml`, with the
+//! same structure. When feedo saves a TOML file, it keeps the comments and
+//! layout of the settings that didn't change.
+
+use std::{fmt, ops::Range, path::Path};
+
+use color_eyre::Result;
+use serde::{Deserialize, de::IntoDeserializer};
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table, Value};
+
+use super::Config;
+
+/// Format of the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// `config.json`, the default.
+    Json,
+    /// `config.toml`, which allows comments.
+    Toml,
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Json => "json",
+            Self::Toml => "toml",
+        })
+    }
+}
+
+impl ConfigFormat {
+    /// Parse a format name as given on the command line.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+
+    /// Format of a config file, by its extension.
+    #[must_use]
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::Toml,
+            _ => Self::Json,
+        }
+    }
+
+    /// Name of the config file in this format.
+    #[must_use]
+    pub const fn file_name(self) -> &'static str {
+        match self {
+            Self::Json => "config.json",
+            Self::Toml => "config.toml",
+        }
+    }
+
+    /// The other format.
+    #[must_use]
+    pub const fn other(self) -> Self {
+        match self {
+            Self::Json => Self::Toml,
+            Self::Toml => Self::Json,
+        }
+    }
+
+    /// Parse a config file without interpreting it yet.
+    pub(super) fn parse(self, source: &str) -> Result<serde_json::Value> {
+        Ok(match self {
+            Self::Json => serde_json::from_str(source)?,
+            Self::Toml => toml_edit::de::from_str(source)?,
+        })
+    }
+
+    /// Write a config in this format.
+    ///
+    /// For TOML, `previous` is the file being replaced; its comments and
+    /// layout are kept wherever the settings are the same.
+    pub(super) fn write(self, config: &Config, previous: Option<&str>) -> Result<String> {
+        match self {
+            Self::Json => Ok(serde_json::to_string_pretty(config)?),
+            Self::Toml => {
+                let content = toml_edit::ser::to_string_pretty(config)?;
+                let Some(mut document) = previous.and_then(|p| p.parse::<DocumentMut>().ok())
+                else {
+                    return Ok(content);
+                };
+                let new: DocumentMut = content.parse()?;
+                merge_table(document.as_table_mut(), new.as_table().clone());
+                Ok(document.to_string())
+            }
+        }
+    }
+
+    /// Line on which the value at `path`, like `folders[0].feeds[2].url`,
+    /// starts in `source`.
+    pub(super) fn line(self, source: &str, path: &str) -> Option<usize> {
+        match self {
+            Self::Json => json_line(source, path),
+            Self::Toml => toml_line(source, path),
+        }
+    }
+}
+
+/// Copy `new` into `old`, keeping the comments and layout of what is in both.
+fn merge_table(old: &mut Table, new: Table) {
+    old.retain(|key, _| new.contains_key(key));
+    for (key, item) in new {
+        match (old.get_mut(&key), item) {
+            (Some(Item::Table(old)), Item::Table(new)) => merge_table(old, new),
+            (Some(Item::ArrayOfTables(old)), Item::ArrayOfTables(new)) => merge_tables(old, new),
+            (Some(Item::Value(old)), Item::Value(mut new)) => {
+                if !same_value(old, &new) {
+                    *new.decor_mut() = old.decor().clone();
+                    *old = new;
+                }
+            }
+            // New, or written differently, like `feeds = []` becoming `[[feeds]]`
+            (_, item) => {
+                old.remove(&key);
+                old.insert(&key, item);
+            }
+        }
+    }
+}
+
+/// Merge `[[...]]` tables, pairing each new one with the old one for the same
+/// `url`, else the same `name`, else in the same place. Old tables left over
+/// are removed.
+fn merge_tables(old: &mut ArrayOfTables, new: ArrayOfTables) {
+    let new: Vec<Table> = new.into_iter().collect();
+    let mut unpaired: Vec<Option<Table>> = std::mem::take(old).into_iter().map(Some).collect();
+
+    let mut pairs: Vec<Option<Table>> = vec![None; new.len()];
+    for key in ["url", "name"] {
+        for (pair, table) in pairs.iter_mut().zip(&new) {
+            let Some(id) = table.get(key).and_then(Item::as_str) else {
+                continue;
+            };
+            if pair.is_none() {
+                *pair = unpaired
+                    .iter_mut()
+                    .find(|t| {
+                        t.as_ref().and_then(|t| t.get(key)).and_then(Item::as_str) == Some(id)
+                    })
+                    .and_then(Option::take);
+            }
+        }
+    }
+    for (pair, slot) in pairs.iter_mut().zip(&mut unpaired) {
+        if pair.is_none() {
+            *pair = slot.take();
+        }
+    }
+
+    for (table, pair) in new.into_iter().zip(pairs) {
+        match pair {
+            Some(mut existing) => {
+                merge_table(&mut existing, table);
+                old.push(existing);
+            }
+            None => old.push(table),
+        }
+    }
+}
+
+/// Whether two values are equal, however they are written.
+fn same_value(a: &Value, b: &Value) -> bool {
+    let plain = |v: &Value| serde_json::Value::deserialize(v.clone().into_deserializer()).ok();
+    plain(a).is_some_and(|a| Some(a) == plain(b))
+}
+
+/// Line on which the value at `path` starts in the TOML `source`.
+fn toml_line(source: &str, path: &str) -> Option<usize> {
+    let document = toml_edit::Document::parse(source).ok()?;
+    let mut node = Node::Item(document.as_item());
+    let mut span = None;
+    for segment in segments(path)? {
+        let (next, next_span) = node.child(&segment)?;
+        node = next;
+        span = next_span.or(span);
+    }
+    span.map(|span: Range<usize>| line_at(source, span.start))
+}
+
+/// A place in a TOML document.
+enum Node<'a> {
+    Item(&'a Item),
+    Table(&'a Table),
+    Value(&'a Value),
+}
+
+impl Node<'_> {
+    fn child(&self, segment: &Segment) -> Option<(Self, Option<Range<usize>>)> {
+        match (segment, self) {
+            (Segment::Key(key), _) => {
+                let table: &dyn toml_edit::TableLike = match self {
+                    Node::Item(item) => item.as_table_like()?,
+                    Node::Table(table) => *table,
+                    Node::Value(value) => value.as_inline_table()?,
+                };
+                let (key, item) = table.get_key_value(key)?;
+                Some((Node::Item(item), key.span()))
+            }
+            (Segment::Index(n), Node::Item(Item::ArrayOfTables(tables))) => {
+                let table = tables.get(*n)?;
+                Some((Node::Table(table), table.span()))
+            }
+            (
+                Segment::Index(n),
+                Node::Item(Item::Value(Value::Array(array))) | Node::Value(Value::Array(array)),
+            ) => {
+                let value = array.get(*n)?;
+                Some((Node::Value(value), value.span()))
+            }
+            (Segment::Index(_), _) => None,
+        }
+    }
+}
+
+enum Segment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// Split a path like `folders[0].feeds[2].url` into keys and indexes.
+fn segments(path: &str) -> Option<Vec<Segment<'_>>> {
+    let mut segments = Vec::new();
+    for part in path.split('.').filter(|p| !p.is_empty()) {
+        let (key, indexes) = part
+            .split_once('[')
+            .map_or((part, ""), |(k, rest)| (k, rest));
+        if !key.is_empty() {
+            segments.push(Segment::Key(key));
+        }
+        for index in indexes.split('[') {
+            if let Some(n) = index.strip_suffix(']') {
+                segments.push(Segment::Index(n.parse().ok()?));
+            }
+        }
+    }
+    Some(segments)
+}
+
+fn line_at(source: &str, offset: usize) -> usize {
+    source[..offset].matches('\n').count() + 1
+}
+
+/// Line on which the value at `path` starts in the JSON `source`.
+fn json_line(source: &str, path: &str) -> Option<usize> {
+    let segments = segments(path)?;
+    let mut cursor = Cursor {
+        bytes: source.as_bytes(),
+        pos: 0,
+    };
+    cursor.find(&segments)?;
+    cursor.skip_ws();
+    Some(line_at(source, cursor.pos))
+}
+
+/// Just enough of a JSON reader to find where a value starts.
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_ws(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_ws();
+        let found = self.peek() == Some(byte);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    /// Read a string, returning it with escapes left as they are.
+    fn string(&mut self) -> Option<&'a str> {
+        if !self.eat(b'"') {
+            return None;
+        }
+        let start = self.pos;
+        loop {
+            match self.peek()? {
+                b'\\' => self.pos += 2,
+                b'"' => break,
+                _ => self.pos += 1,
+            }
+        }
+        self.pos += 1;
+        std::str::from_utf8(&self.bytes[start..self.pos - 1]).ok()
+    }
+
+    fn skip_value(&mut self) -> Option<()> {
+        self.skip_ws();
+        match self.peek()? {
+            b'"' => {
+                self.string()?;
+            }
+            b'{' | b'[' => {
+                let mut depth = 0_usize;
+                loop {
+                    match self.peek()? {
+                        b'"' => {
+                            self.string()?;
+                            continue;
+                        }
+                        b'{' | b'[' => depth += 1,
+                        b'}' | b']' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                self.pos += 1;
+                                return Some(());
+                            }
+                        }
+                        _ => {}
+                    }
+                    self.pos += 1;
+                }
+            }
+            _ => {
+                while !matches!(self.peek(), None | Some(b',' | b'}' | b']')) {
+                    self.pos += 1;
+                }
+            }
+        }
+        Some(())
+    }
+
+    /// Move to the value at `path` below the current one.
+    fn find(&mut self, path: &[Segment]) -> Option<()> {
+        let Some((first, rest)) = path.split_first() else {
+            return Some(());
+        };
+        match first {
+            Segment::Key(key) => {
+                if !self.eat(b'{') {
+                    return None;
+                }
+                loop {
+                    let name = self.string()?;
+                    if !self.eat(b':') {
+                        return None;
+                    }
+                    if name == *key {
+                        return self.find(rest);
+                    }
+                    self.skip_value()?;
+                    if !self.eat(b',') {
+                        return None;
+                    }
+                }
+            }
+            Segment::Index(n) => {
+                if !self.eat(b'[') {
+                    return None;
+                }
+                for _ in 0..*n {
+                    self.skip_value()?;
+                    if !self.eat(b',') {
+                        return None;
+                    }
+                }
+                self.find(rest)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toml_save_keeps_comments() {
+        let source = r#"# Kept in git
+version = 1
+refresh_interval = 15 # minutes
+
+# Reading list
+[[feeds]]
+name = "Lobsters"
+url = "https://lobste.rs/rss"
+"#;
+        let value = ConfigFormat::Toml.parse(source).unwrap();
+        let mut config: Config = serde_json::from_value(value).unwrap();
+        assert_eq!(config.refresh_interval, 15);
+        assert_eq!(ConfigFormat::Toml.line(source, "feeds[0].url"), Some(8));
+
+        config.feeds[0].name = "Lobste.rs".to_string();
+        let saved = ConfigFormat::Toml.write(&config, Some(source)).unwrap();
+        assert!(saved.starts_with("# Kept in git\n"));
+        assert!(saved.contains("refresh_interval = 15 # minutes\n"));
+        assert!(saved.contains("# Reading list\n[[feeds]]\nname = \"Lobste.rs\"\n"));
+
+        let value = ConfigFormat::Toml.parse(&saved).unwrap();
+        let reloaded: Config = serde_json::from_value(value).unwrap();
+        assert_eq!(reloaded.feeds[0].name, "Lobste.rs");
+        assert_eq!(reloaded.folders.len(), config.folders.len());
+    }
+
+    #[test]
+    fn test_toml_save_keeps_comments_with_their_feed() {
+        let source = r#"version = 1
+
+# Daily
+[[feeds]]
+name = "Lobsters"
+url = "https://lobste.rs/rss"
+
+# Weekly
+[[feeds]]
+name = "LWN"
+url = "https://lwn.net/headlines/rss"
+"#;
+        let value = ConfigFormat::Toml.parse(source).unwrap();
+        let mut config: Config = serde_json::from_value(value).unwrap();
+
+        // Removing the first feed keeps the second one's comment
+        config.feeds.remove(0);
+        let saved = ConfigFormat::Toml.write(&config, Some(source)).unwrap();
+        assert!(saved.contains("# Weekly\n[[feeds]]\nname = \"LWN\"\n"));
+        assert!(!saved.contains("# Daily"));
+
+        // So does adding one in front of it
+        config.feeds.insert(
+            0,
+            crate::config::FeedConfig {
+                name: "HN".to_string(),
+                url: "https://news.ycombinator.com/rss".to_string(),
+                ..Default::default()
+            },
+        );
+        let saved = ConfigFormat::Toml.write(&config, Some(&saved)).unwrap();
+        assert!(saved.contains("# Weekly\n[[feeds]]\nname = \"LWN\"\n"));
+        let value = ConfigFormat::Toml.parse(&saved).unwrap();
+        let reloaded: Config = serde_json::from_value(value).unwrap();
+        let names: Vec<&str> = reloaded.feeds.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["HN", "LWN"]);
+    }
+}
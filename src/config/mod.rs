@@ -1,7 +1,12 @@
 //! Configuration management.
 //!
-//! Handles loading, saving, and managing application configuration.
+//! Handles loading, saving, and managing application configuration, and
+//! migrating and validating config files.
 
 mod data;
+mod format;
+mod schema;
 
 pub use data::{Config, FeedConfig, FolderConfig};
+pub use format::ConfigFormat;
+pub use schema::{CONFIG_VERSION, ConfigIssue};
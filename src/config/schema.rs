@@ -0,0 +1,229 @@
+//! Config file versions, migrations and validation.
+//!
+//! A release that changes the shape of the config file bumps
+//! [`CONFIG_VERSION`] and adds a migration step, so older files keep
+//! loading. Mistakes are reported with the path and line of the value.
+
+use std::{collections::HashMap, fmt};
+
+use ratatui_themes::ThemeName;
+use reqwest::Url;
+use serde_json::{Map, Value};
+
+use super::{Config, ConfigFormat};
+use crate::http::{ProxySetting, TlsSettings};
+
+/// Version of the config format written by this build.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Migration steps; the one at index `n` upgrades a version `n` file.
+const MIGRATIONS: &[fn(&mut Map<String, Value>)] = &[
+    // 0 → 1: files from before versioning only gain the `version` field
+    |_| {},
+];
+
+const _: () = assert!(MIGRATIONS.len() == CONFIG_VERSION as usize);
+
+/// A problem with a value in the config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    /// Where the value is, like `folders[0].feeds[2].url`.
+    pub path: String,
+
+    /// Line of the value in the file, if it could be found.
+    pub line: Option<usize>,
+
+    /// What is wrong with it.
+    pub message: String,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {line}, ")?;
+        }
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Turn the parsed contents of a config file into a [`Config`].
+///
+/// The file is migrated to the current version first. Errors that leave no
+/// usable config are returned; others are kept in [`Config::issues`].
+pub(super) fn load(
+    source: &str,
+    format: ConfigFormat,
+    mut value: Value,
+) -> Result<Config, ConfigIssue> {
+    let issue = |path: String, message: String| ConfigIssue {
+        line: format.line(source, &path),
+        path,
+        message,
+    };
+
+    let Some(object) = value.as_object_mut() else {
+        return Err(issue(
+            String::new(),
+            "expected an object with the settings".to_string(),
+        ));
+    };
+
+    let version = match object.get("version") {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| issue("version".to_string(), "expected a number".to_string()))?,
+    };
+    if version > CONFIG_VERSION {
+        return Err(issue(
+            "version".to_string(),
+            format!("written by a newer feedo (version {version}); update feedo to use it"),
+        ));
+    }
+    for migrate in &MIGRATIONS[version as usize..] {
+        migrate(object);
+    }
+    object.insert("version".to_string(), CONFIG_VERSION.into());
+
+    // An unknown theme falls back to the default instead of failing
+    let mut issues = Vec::new();
+    if let Some(theme) = object.get_mut("theme").and_then(Value::as_object_mut)
+        && let Some(name) = theme.get("name")
+        && serde_json::from_value::<ThemeName>(name.clone()).is_err()
+    {
+        issues.push(issue(
+            "theme.name".to_string(),
+            format!("unknown theme {name}; using the default"),
+        ));
+        theme.remove("name");
+    }
+
+    let mut config: Config = serde_path_to_error::deserialize(value)
+        .map_err(|e| issue(e.path().to_string(), e.inner().to_string()))?;
+
+    issues.extend(
+        validate(&config)
+            .into_iter()
+            .map(|(path, message)| issue(path, message)),
+    );
+    config.issues = issues;
+    Ok(config)
+}
+
+/// Check for mistakes that still parse, like bad URLs, duplicate feeds,
+/// unusable proxies or unreadable CA files.
+fn validate(config: &Config) -> Vec<(String, String)> {
+    let mut issues = Vec::new();
+    let mut seen: HashMap<&str, String> = HashMap::new();
+
+    let feeds = config
+        .folders
+        .iter()
+        .enumerate()
+        .flat_map(|(i, folder)| {
+            folder
+                .feeds
+                .iter()
+                .enumerate()
+                .map(move |(j, feed)| (format!("folders[{i}].feeds[{j}]"), feed))
+        })
+        .chain(
+            config
+                .feeds
+                .iter()
+                .enumerate()
+                .map(|(j, feed)| (format!("feeds[{j}]"), feed)),
+        );
+
+    for (path, feed) in feeds {
+        let url_path = format!("{path}.url");
+        if !is_web_url(&feed.url) {
+            issues.push((url_path, format!("{:?} is not an http(s) URL", feed.url)));
+        } else if let Some(first) = seen.get(feed.url.as_str()) {
+            issues.push((url_path, format!("duplicate of {first}")));
+        } else {
+            seen.insert(&feed.url, url_path);
+        }
+
+        if feed.proxy.is_some()
+            && let Err(e) = ProxySetting::for_feed(feed, config).validate()
+        {
+            issues.push((format!("{path}.proxy"), e.to_string()));
+        }
+    }
+
+    if let Err(e) = ProxySetting::global(config).validate() {
+        issues.push(("proxy".to_string(), e.to_string()));
+    }
+    issues.extend(TlsSettings::global(config).problems());
+
+    if let Some(sync) = &config.sync
+        && !is_web_url(&sync.server)
+    {
+        issues.push((
+            "sync.server".to_string(),
+            format!("{:?} is not an http(s) URL", sync.server),
+        ));
+    }
+    issues
+}
+
+fn is_web_url(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_str(source: &str) -> Result<Config, ConfigIssue> {
+        load(
+            source,
+            ConfigFormat::Json,
+            serde_json::from_str(source).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_migrates_and_reports_lines() {
+        let source = r#"{
+  "theme": { "name": "draculaa" },
+  "folders": [
+    {
+      "name": "Tech",
+      "feeds": [
+        { "name": "Lobsters", "url": "https://lobste.rs/rss" },
+        { "name": "Typo", "url": "htps://example.com/feed" }
+      ]
+    }
+  ],
+  "feeds": [
+    { "name": "Again", "url": "https://lobste.rs/rss", "proxy": "not a proxy" }
+  ]
+}"#;
+        let config = load_str(source).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.theme.name, ThemeName::default());
+
+        let issues: Vec<String> = config.issues.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            issues[..3],
+            [
+                r#"line 2, theme.name: unknown theme "draculaa"; using the default"#,
+                r#"line 8, folders[0].feeds[1].url: "htps://example.com/feed" is not an http(s) URL"#,
+                "line 13, feeds[0].url: duplicate of folders[0].feeds[0].url",
+            ]
+        );
+        assert!(issues[3].starts_with("line 13, feeds[0].proxy: Invalid proxy"));
+        assert_eq!(issues.len(), 4);
+
+        // Type errors are fatal, and say where they are
+        let error = load_str("{\n  \"version\": 1,\n  \"refresh_interval\": \"5\"\n}").unwrap_err();
+        assert_eq!(error.path, "refresh_interval");
+        assert_eq!(error.line, Some(3));
+
+        let error = load_str(r#"{ "version": 99 }"#).unwrap_err();
+        assert_eq!(error.path, "version");
+    }
+}
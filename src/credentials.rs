@@ -1,6 +1,7 @@
 //! Secure credential storage using AES-256-GCM encryption.
 //!
-//! Credentials (username + password) are encrypted and stored in ~/.config/feedo/.credentials
+//! Credentials (username + password) are encrypted and stored in `.credentials`
+//! in the config directory, so each profile has its own.
 
 use aes_gcm::{
     Aes256Gcm, Nonce,
@@ -151,15 +152,7 @@ pub fn delete_password(key: &str) -> Result<(), String> {
 }
 
 fn credentials_file() -> Option<PathBuf> {
-    let home = std::env::var("HOME")
-        .or_else(|_| std::env::var("USERPROFILE"))
-        .ok()?;
-    Some(
-        PathBuf::from(home)
-            .join(".config")
-            .join("feedo")
-            .join(".credentials"),
-    )
+    crate::config::Config::config_dir().map(|dir| dir.join(".credentials"))
 }
 
 fn derive_key() -> [u8; 32] {
@@ -0,0 +1,586 @@
+//! Enclosure downloads.
+//!
+//! Downloads run in spawned tasks, a few at a time, and report progress over
+//! a channel that the main loop drains every tick. Data goes to a `.part`
+//! file next to the target and is renamed once complete, so an interrupted
+//! download picks up where it left off with an HTTP range request. The
+//! file's `ETag` or `Last-Modified` date is kept beside the `.part` file and sent
+//! as `If-Range`, so a file that changed on the server is fetched afresh
+//! instead of being spliced onto the old bytes.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+};
+
+use color_eyre::{Result, eyre::eyre};
+use reqwest::{StatusCode, header};
+use tokio::{
+    io::AsyncWriteExt,
+    process::Command,
+    sync::{Semaphore, mpsc},
+    task::JoinHandle,
+};
+use tracing::{debug, warn};
+
+use crate::config::Config;
+use crate::feed::{Enclosure, sanitize_file_name};
+use crate::http::{self, ClientSettings};
+
+/// Number of downloads that run at the same time.
+const MAX_CONCURRENT: usize = 2;
+
+/// Minimum number of bytes between progress updates.
+const PROGRESS_STEP: u64 = 256 * 1024;
+
+/// State of a download.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DownloadState {
+    /// Waiting for a free slot.
+    Queued,
+    /// Transferring data.
+    Running,
+    /// Saved to disk.
+    Done,
+    /// Stopped by an error; can be resumed.
+    Failed(String),
+    /// Stopped by the user; can be resumed.
+    Cancelled,
+}
+
+/// A single enclosure download.
+#[derive(Debug)]
+pub struct Download {
+    /// Enclosure URL.
+    pub url: String,
+
+    /// Title of the item the enclosure belongs to.
+    pub title: String,
+
+    /// Where the file is saved.
+    pub path: PathBuf,
+
+    /// Current state.
+    pub state: DownloadState,
+
+    /// Bytes on disk so far.
+    pub downloaded: u64,
+
+    /// Total size, if known.
+    pub total: Option<u64>,
+
+    task: Option<JoinHandle<()>>,
+}
+
+impl Download {
+    /// Progress as a fraction between 0 and 1, if the size is known.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn progress(&self) -> Option<f64> {
+        self.total
+            .filter(|&t| t > 0)
+            .map(|t| (self.downloaded as f64 / t as f64).min(1.0))
+    }
+
+    /// Whether the download is queued or running.
+    #[must_use]
+    pub const fn is_active(&self) -> bool {
+        matches!(self.state, DownloadState::Queued | DownloadState::Running)
+    }
+}
+
+/// Progress reported by a download task.
+#[derive(Debug)]
+enum Progress {
+    Started { downloaded: u64, total: Option<u64> },
+    Data(u64),
+    Done,
+    Failed(String),
+}
+
+/// Queue of enclosure downloads.
+pub struct DownloadManager {
+    /// Client for downloads, or why it couldn't be built.
+    client: std::result::Result<reqwest::Client, String>,
+    dir: Option<PathBuf>,
+    downloads: Vec<Download>,
+    slots: Arc<Semaphore>,
+    tx: mpsc::UnboundedSender<(String, Progress)>,
+    rx: mpsc::UnboundedReceiver<(String, Progress)>,
+}
+
+impl DownloadManager {
+    /// Create a download manager using the configured directory and network settings.
+    ///
+    /// If the HTTP client can't be built, e.g. because the proxy is invalid,
+    /// downloads fail with the reason.
+    #[must_use]
+    pub fn new(config: &Config) -> Self {
+        // No overall timeout: episodes can take a long time to download
+        let client = http::client_builder(&ClientSettings::global(config))
+            .and_then(|builder| {
+                Ok(builder
+                    .connect_timeout(std::time::Duration::from_secs(30))
+                    .build()?)
+            })
+            .map_err(|e| {
+                warn!("Downloads are unavailable: {e}");
+                e.to_string()
+            });
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        Self {
+            client,
+            dir: config.download_dir(),
+            downloads: Vec::new(),
+            slots: Arc::new(Semaphore::new(MAX_CONCURRENT)),
+            tx,
+            rx,
+        }
+    }
+
+    /// All downloads, oldest first.
+    #[must_use]
+    pub fn downloads(&self) -> &[Download] {
+        &self.downloads
+    }
+
+    /// The download of an enclosure, if it has been queued.
+    #[must_use]
+    pub fn get(&self, url: &str) -> Option<&Download> {
+        self.downloads.iter().find(|d| d.url == url)
+    }
+
+    /// Number of queued or running downloads.
+    #[must_use]
+    pub fn active_count(&self) -> usize {
+        self.downloads.iter().filter(|d| d.is_active()).count()
+    }
+
+    /// Where an enclosure of the given feed is saved.
+    #[must_use]
+    pub fn path_for(&self, feed_name: &str, enclosure: &Enclosure) -> Option<PathBuf> {
+        let folder = sanitize_file_name(feed_name);
+        let folder = if folder.is_empty() { "feed" } else { &folder };
+        Some(
+            self.dir
+                .as_ref()?
+                .join(folder)
+                .join(enclosure.download_name()),
+        )
+    }
+
+    /// The downloaded file for an enclosure, if it is on disk.
+    #[must_use]
+    pub fn local_file(&self, feed_name: &str, enclosure: &Enclosure) -> Option<PathBuf> {
+        self.path_for(feed_name, enclosure).filter(|p| p.is_file())
+    }
+
+    /// Queue an enclosure for download.
+    ///
+    /// Returns `false` if it is already downloading or on disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no download directory could be determined.
+    pub fn enqueue(&mut self, feed_name: &str, title: &str, enclosure: &Enclosure) -> Result<bool> {
+        let path = self
+            .path_for(feed_name, enclosure)
+            .ok_or_else(|| eyre!("No download directory; set download_dir in the config"))?;
+
+        if let Some(index) = self.downloads.iter().position(|d| d.url == enclosure.url) {
+            if self.downloads[index].is_active() || path.is_file() {
+                return Ok(false);
+            }
+            self.downloads.remove(index);
+        } else if path.is_file() {
+            return Ok(false);
+        }
+
+        self.downloads.push(Download {
+            url: enclosure.url.clone(),
+            title: title.to_string(),
+            path,
+            state: DownloadState::Queued,
+            downloaded: 0,
+            total: enclosure.length,
+            task: None,
+        });
+        self.start(self.downloads.len() - 1);
+        Ok(true)
+    }
+
+    /// Stop a download, keeping the partial file so it can be resumed.
+    pub fn cancel(&mut self, index: usize) {
+        if let Some(download) = self.downloads.get_mut(index) {
+            if let Some(task) = download.task.take() {
+                task.abort();
+            }
+            if download.is_active() {
+                download.state = DownloadState::Cancelled;
+            }
+        }
+    }
+
+    /// Resume a failed or cancelled download.
+    pub fn resume(&mut self, index: usize) {
+        if self
+            .downloads
+            .get(index)
+            .is_some_and(|d| matches!(d.state, DownloadState::Failed(_) | DownloadState::Cancelled))
+        {
+            self.downloads[index].state = DownloadState::Queued;
+            self.start(index);
+        }
+    }
+
+    /// Apply progress reported by running downloads.
+    ///
+    /// Returns the indices of downloads that finished or failed.
+    pub fn poll(&mut self) -> Vec<usize> {
+        let mut finished = Vec::new();
+
+        while let Ok((url, progress)) = self.rx.try_recv() {
+            let Some(index) = self.downloads.iter().position(|d| d.url == url) else {
+                continue;
+            };
+            let download = &mut self.downloads[index];
+            // Ignore stragglers from a task that was cancelled
+            if !download.is_active() {
+                continue;
+            }
+
+            match progress {
+                Progress::Started { downloaded, total } => {
+                    download.state = DownloadState::Running;
+                    download.downloaded = downloaded;
+                    download.total = total.or(download.total);
+                }
+                Progress::Data(downloaded) => download.downloaded = downloaded,
+                Progress::Done => {
+                    download.state = DownloadState::Done;
+                    download.task = None;
+                    finished.push(index);
+                }
+                Progress::Failed(error) => {
+                    warn!("Download of {url} failed: {error}");
+                    download.state = DownloadState::Failed(error);
+                    download.task = None;
+                    finished.push(index);
+                }
+            }
+        }
+
+        finished
+    }
+
+    /// Spawn the task for a queued download.
+    fn start(&mut self, index: usize) {
+        let download = &mut self.downloads[index];
+        let client = match &self.client {
+            Ok(client) => client.clone(),
+            Err(e) => {
+                download.state = DownloadState::Failed(e.clone());
+                return;
+            }
+        };
+        let slots = Arc::clone(&self.slots);
+        let tx = self.tx.clone();
+        let url = download.url.clone();
+        let path = download.path.clone();
+        let expected = download.total;
+
+        download.task = Some(tokio::spawn(async move {
+            let Ok(_permit) = slots.acquire_owned().await else {
+                return;
+            };
+            let progress = match fetch(&client, &url, &path, expected, &tx).await {
+                Ok(()) => Progress::Done,
+                Err(e) => Progress::Failed(e.to_string()),
+            };
+            let _ = tx.send((url, progress));
+        }));
+    }
+}
+
+/// Path of the partial file for a download.
+fn part_path(path: &Path) -> PathBuf {
+    let mut part = path.as_os_str().to_owned();
+    part.push(".part");
+    PathBuf::from(part)
+}
+
+/// Path of the file holding the validator of a partial download.
+fn validator_path(path: &Path) -> PathBuf {
+    let mut validator = part_path(path).into_os_string();
+    validator.push(".validator");
+    PathBuf::from(validator)
+}
+
+/// Validator to send as `If-Range`: a strong `ETag`, or else `Last-Modified`.
+///
+/// Weak `ETag`s can't be used for range requests.
+fn range_validator(response: &reqwest::Response) -> Option<&str> {
+    let headers = response.headers();
+    headers
+        .get(header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| {
+            headers
+                .get(header::LAST_MODIFIED)
+                .and_then(|v| v.to_str().ok())
+        })
+}
+
+/// First byte and total size from a `Content-Range: bytes <start>-<end>/<size>` header.
+///
+/// The size is `None` if the server doesn't know it (`*`).
+fn content_range(response: &reqwest::Response) -> Option<(u64, Option<u64>)> {
+    let range = response
+        .headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?;
+    let (range, size) = range.strip_prefix("bytes ")?.split_once('/')?;
+    let start = range.split_once('-')?.0.trim().parse().ok()?;
+    Some((start, size.trim().parse().ok()))
+}
+
+/// Total size from a `Content-Range: bytes */<size>` header.
+fn complete_length(response: &reqwest::Response) -> Option<u64> {
+    let range = response
+        .headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?;
+    range.rsplit_once('/')?.1.trim().parse().ok()
+}
+
+/// Download `url` to `path`, resuming from a partial file if there is one.
+///
+/// `expected` is the size announced by the feed, if any.
+async fn fetch(
+    client: &reqwest::Client,
+    url: &str,
+    path: &Path,
+    expected: Option<u64>,
+    tx: &mpsc::UnboundedSender<(String, Progress)>,
+) -> Result<()> {
+    let part = part_path(path);
+    let validator = validator_path(path);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let existing = tokio::fs::metadata(&part).await.map_or(0, |m| m.len());
+    let mut request = client.get(url);
+    if existing > 0 {
+        request = request.header(header::RANGE, format!("bytes={existing}-"));
+        // Only resume if the file hasn't changed since the partial download
+        if let Ok(value) = tokio::fs::read_to_string(&validator).await {
+            request = request.header(header::IF_RANGE, value.trim());
+        }
+    }
+    let mut response = request.send().await?;
+
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE && existing > 0 {
+        // The partial file may already hold everything
+        if complete_length(&response).or(expected) == Some(existing) {
+            tokio::fs::rename(&part, path).await?;
+            let _ = tokio::fs::remove_file(&validator).await;
+            return Ok(());
+        }
+
+        // Otherwise it doesn't match the file on the server; start over
+        debug!("Discarding stale partial download of {url}");
+        tokio::fs::remove_file(&part).await?;
+        response = client.get(url).send().await?;
+    } else if response.status() == StatusCode::PARTIAL_CONTENT
+        && content_range(&response).map(|(start, _)| start) != Some(existing)
+    {
+        // A range that doesn't continue the partial file can't be appended
+        debug!("Server sent the wrong range of {url}; starting over");
+        response = client.get(url).send().await?;
+    }
+    let response_status = response.status();
+    if !response_status.is_success() {
+        return Err(eyre!("HTTP {response_status}"));
+    }
+
+    // Servers that ignore the range, or whose file changed, send the whole
+    // file again
+    let resumed = response_status == StatusCode::PARTIAL_CONTENT && existing > 0;
+    let mut downloaded = if resumed { existing } else { 0 };
+    let total = content_range(&response)
+        .and_then(|(_, size)| size)
+        .or_else(|| response.content_length().map(|len| len + downloaded));
+    debug!("Downloading {url} from byte {downloaded}");
+
+    let mut file = if resumed {
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&part)
+            .await?
+    } else {
+        match range_validator(&response) {
+            Some(value) => tokio::fs::write(&validator, value).await?,
+            None => {
+                let _ = tokio::fs::remove_file(&validator).await;
+            }
+        }
+        tokio::fs::File::create(&part).await?
+    };
+    let _ = tx.send((url.to_string(), Progress::Started { downloaded, total }));
+
+    let mut reported = downloaded;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+        downloaded += chunk.len() as u64;
+        if downloaded - reported >= PROGRESS_STEP {
+            reported = downloaded;
+            let _ = tx.send((url.to_string(), Progress::Data(downloaded)));
+        }
+    }
+    file.flush().await?;
+    drop(file);
+
+    if total.is_some_and(|t| downloaded < t) {
+        return Err(eyre!("Connection closed after {downloaded} bytes"));
+    }
+
+    tokio::fs::rename(&part, path).await?;
+    let _ = tokio::fs::remove_file(&validator).await;
+    Ok(())
+}
+
+/// Hand a file or URL to the configured player.
+///
+/// The command is split on whitespace and the target appended as the last
+/// argument. The player runs detached from the terminal, and a task waits
+/// for it so it doesn't linger as a zombie. Must be called from within a
+/// Tokio runtime.
+///
+/// # Errors
+///
+/// Returns an error if the command is empty or cannot be started.
+pub fn play(command: &str, target: &str) -> Result<()> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next().ok_or_else(|| eyre!("No player configured"))?;
+
+    let mut child = Command::new(program)
+        .args(parts)
+        .arg(target)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| eyre!("Failed to start {program}: {e}"))?;
+    tokio::spawn(async move {
+        let _ = child.wait().await;
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    use super::*;
+
+    /// Serve `body` with an `ETag`, honouring `Range: bytes=N-` and `If-Range`.
+    ///
+    /// With `from_start`, ranges are answered from the first byte instead.
+    fn serve(body: &'static [u8], etag: &'static str, from_start: bool) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut buf = [0; 1024];
+                let n = stream.read(&mut buf).unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                let header = |name: &str| {
+                    request
+                        .lines()
+                        .find_map(|l| l.strip_prefix(name).map(str::to_string))
+                };
+
+                let start = header("range: bytes=")
+                    .and_then(|r| r.trim_end_matches('-').parse::<usize>().ok())
+                    .filter(|_| header("if-range: ").is_none_or(|v| v == etag))
+                    .map(|start| if from_start { 0 } else { start });
+                let response = match start {
+                    Some(start) if start >= body.len() => format!(
+                        "HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */{}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    ),
+                    Some(start) => format!(
+                        "HTTP/1.1 206 Partial Content\r\nETag: {etag}\r\nContent-Range: bytes {start}-{}/{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len() - 1,
+                        body.len(),
+                        body.len() - start
+                    ),
+                    None => format!(
+                        "HTTP/1.1 200 OK\r\nETag: {etag}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    ),
+                };
+                stream.write_all(response.as_bytes()).unwrap();
+                if !response.starts_with("HTTP/1.1 416") {
+                    stream.write_all(&body[start.unwrap_or(0)..]).unwrap();
+                }
+            }
+        });
+
+        format!("http://{addr}/episode.mp3")
+    }
+
+    #[tokio::test]
+    async fn test_resumes_partial_download() {
+        let dir = std::env::temp_dir().join(format!("feedo-download-{}", std::process::id()));
+        let path = dir.join("episode.mp3");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(part_path(&path), b"hello ").unwrap();
+        std::fs::write(validator_path(&path), "\"v1\"").unwrap();
+
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let url = serve(b"hello world", "\"v1\"", false);
+        let client = reqwest::Client::new();
+        fetch(&client, &url, &path, None, &tx).await.unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello world");
+        assert!(!part_path(&path).exists());
+        assert!(!validator_path(&path).exists());
+
+        // A partial file longer than the one on the server is discarded
+        std::fs::remove_file(&path).unwrap();
+        std::fs::write(part_path(&path), b"hello there, world").unwrap();
+        fetch(&client, &url, &path, None, &tx).await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello world");
+
+        // So is one of a file that has changed since
+        std::fs::remove_file(&path).unwrap();
+        std::fs::write(part_path(&path), b"howdy ").unwrap();
+        std::fs::write(validator_path(&path), "\"v0\"").unwrap();
+        fetch(&client, &url, &path, None, &tx).await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello world");
+
+        // And a range that doesn't continue the partial file isn't appended
+        std::fs::remove_file(&path).unwrap();
+        std::fs::write(part_path(&path), b"hello ").unwrap();
+        let url = serve(b"hello world", "\"v1\"", true);
+        fetch(&client, &url, &path, None, &tx).await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello world");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_play_needs_a_command() {
+        assert!(play("  ", "https://example.com/a.mp3").is_err());
+    }
+}
@@ -10,7 +10,20 @@ use color_eyre::Result;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, warn};
 
+use super::item::is_legacy_id;
+use super::store::{self, Changes};
+use super::{Enclosure, FeedItem, FeedMeta, KnownItem, Retention, ScheduleHints};
 use crate::config::Config;
+use crate::persist;
+
+/// File name of the cache database in the data directory.
+const DB_FILE: &str = "cache.db";
+
+/// How often the cache database is backed up, at most.
+const BACKUP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+/// Days the read state of a pruned item is remembered, in case it comes back.
+const READ_TOMBSTONE_DAYS: i64 = 180;
 
 /// Cached feed data.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +35,7 @@ pub struct CachedFeed {
     pub name: String,
 
     /// Cached items.
+    #[serde(default)]
     pub items: Vec<CachedItem>,
 
     /// Last successful fetch time.
@@ -30,12 +44,63 @@ pub struct CachedFeed {
     /// Last fetch error (if any).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_error: Option<String>,
+
+    /// Number of fetches that have failed in a row.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub failures: u32,
+
+    /// When the current run of failures started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failing_since: Option<DateTime<Utc>>,
+
+    /// HTTP status of the last response, if the server answered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+
+    /// Where the feed has been permanently redirected to, if anywhere.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moved_to: Option<String>,
+
+    /// Number of fetches in a row that were permanently redirected to `moved_to`.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub moved_count: u32,
+
+    /// `ETag` header from the last successful fetch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+
+    /// `Last-Modified` header from the last successful fetch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+
+    /// Refresh hints from the last successful fetch.
+    #[serde(default, skip_serializing_if = "ScheduleHints::is_empty")]
+    pub schedule: ScheduleHints,
+
+    /// Channel metadata from the last successful fetch.
+    #[serde(default, skip_serializing_if = "FeedMeta::is_empty")]
+    pub meta: FeedMeta,
+
+    /// IDs of read items that were pruned, with when, so they are still read
+    /// if the feed brings them back. Expire after [`READ_TOMBSTONE_DAYS`].
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub read_tombstones: HashMap<String, DateTime<Utc>>,
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+const fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+const fn is_false(b: &bool) -> bool {
+    !*b
 }
 
 /// Cached item data.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CachedItem {
-    /// Unique ID (generated from link or title hash).
+    /// Unique ID (hash of the guid, link or title).
     pub id: String,
 
     /// Article title.
@@ -45,36 +110,72 @@ pub struct CachedItem {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub link: Option<String>,
 
+    /// Other links, e.g. comments or translations.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternate_links: Vec<String>,
+
     /// Publication date.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub published: Option<DateTime<Utc>>,
 
-    /// Summary or content.
+    /// Last update date.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated: Option<DateTime<Utc>>,
+
+    /// Author names.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<String>,
+
+    /// Category or tag names.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+
+    /// Summary.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub summary: Option<String>,
 
+    /// Full content.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+
+    /// Attached media.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub enclosures: Vec<Enclosure>,
+
+    /// Hash of the title and text; see [`FeedItem::hash_content`].
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub content_hash: String,
+
+    /// Whether the text changed after the item was read.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub changed: bool,
+
+    /// The text as it was read, before the latest change.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_body: Option<String>,
+
+    /// Whether the item has dropped out of the feed and is only kept here.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub archived: bool,
+
+    /// Whether the item is starred.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub starred: bool,
+
     /// Whether the item has been read.
     #[serde(default)]
     pub read: bool,
 
-    /// When this item was first cached.
+    /// When this item was first seen. Kept across refreshes; retention ages
+    /// items by it.
     pub cached_at: DateTime<Utc>,
 }
 
 impl CachedItem {
-    /// Generate a unique ID for an item.
+    /// Generate a stable ID for an item; see [`FeedItem::generate_id`].
     #[must_use]
-    pub fn generate_id(link: Option<&str>, title: &str) -> String {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-
-        let mut hasher = DefaultHasher::new();
-        if let Some(link) = link {
-            link.hash(&mut hasher);
-        } else {
-            title.hash(&mut hasher);
-        }
-        format!("{:x}", hasher.finish())
+    pub fn generate_id(guid: Option<&str>, link: Option<&str>, title: &str) -> String {
+        FeedItem::generate_id(guid, link, title)
     }
 }
 
@@ -84,68 +185,140 @@ pub struct FeedCache {
     /// Cached feeds by URL.
     feeds: HashMap<String, CachedFeed>,
 
-    /// Whether cache has been modified.
-    dirty: bool,
+    /// What changed since the last save.
+    changes: Changes,
+
+    /// Whether saving is disabled because another instance owns the cache.
+    read_only: bool,
 }
 
 impl FeedCache {
     /// Load cache from disk.
     ///
+    /// A `cache.json` left by older versions is moved into the database first.
+    /// If `SQLite` reports the database as corrupt, it is moved aside and the
+    /// newest backup that loads takes its place.
+    ///
     /// # Errors
     ///
-    /// Returns an error if the cache file exists but cannot be read or parsed.
+    /// Returns an error if the database can't be opened (e.g. it is from a
+    /// newer feedo), it is corrupt and none of its backups can be read, or the
+    /// old cache file cannot be migrated.
     pub fn load() -> Result<Self> {
-        let path = Self::cache_path()?;
+        let dir = Self::cache_dir()?;
+        let path = dir.join(DB_FILE);
+        let json_path = dir.join("cache.json");
+        let feeds = persist::load_or_restore(
+            &path,
+            |path| {
+                let mut conn = store::open(path)?;
+                store::migrate_json(&mut conn, &json_path)?;
+                let feeds = store::load(&conn)?;
 
-        if !path.exists() {
-            debug!("No cache file found, starting fresh");
-            return Ok(Self::default());
-        }
+                // Back up a good database about once a day
+                let stale = persist::backups(path).first().is_none_or(|newest| {
+                    fs::metadata(newest)
+                        .and_then(|m| m.modified())
+                        .map_or(true, |at| {
+                            at.elapsed().unwrap_or_default() > BACKUP_INTERVAL
+                        })
+                });
+                if stale && !feeds.is_empty() {
+                    store::backup(&conn, &persist::next_backup(path)?)?;
+                }
+                Ok(feeds)
+            },
+            store::is_corrupt,
+        )?;
+        debug!("Loaded {} feeds from cache", feeds.len());
 
-        let content = fs::read_to_string(&path)?;
-        let feeds: HashMap<String, CachedFeed> = serde_json::from_str(&content)?;
+        Ok(Self {
+            feeds,
+            changes: Changes::default(),
+            read_only: false,
+        })
+    }
 
-        debug!("Loaded {} feeds from cache", feeds.len());
+    /// Load the cache without changing anything on disk, for when another
+    /// instance owns it.
+    ///
+    /// The database is opened read-only: nothing is migrated, backed up or
+    /// restored, and [`Self::save`] does nothing. A missing database gives an
+    /// empty cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database exists but cannot be read.
+    pub fn load_read_only() -> Result<Self> {
+        let path = Self::cache_dir()?.join(DB_FILE);
+        let feeds = if path.exists() {
+            store::load(&store::open_read_only(&path)?)?
+        } else {
+            HashMap::new()
+        };
+        debug!("Loaded {} feeds from cache (read-only)", feeds.len());
 
         Ok(Self {
             feeds,
-            dirty: false,
+            changes: Changes::default(),
+            read_only: true,
         })
     }
 
-    /// Save cache to disk.
+    /// Save what changed since the last save to disk.
+    ///
+    /// Does nothing once [`Self::set_read_only`] has been called.
     ///
     /// # Errors
     ///
-    /// Returns an error if the cache file cannot be written.
+    /// Returns an error if the database cannot be written.
     pub fn save(&mut self) -> Result<()> {
-        if !self.dirty {
+        if self.changes.is_empty() || self.read_only {
             return Ok(());
         }
 
-        let path = Self::cache_path()?;
+        let mut conn = store::open(&Self::cache_dir()?.join(DB_FILE))?;
+        store::save(&mut conn, &self.feeds, &self.changes)?;
 
-        // Ensure directory exists
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-        }
+        self.changes.clear();
+        debug!("Saved cache ({} feeds)", self.feeds.len());
 
-        let content = serde_json::to_string_pretty(&self.feeds)?;
-        fs::write(&path, content)?;
+        Ok(())
+    }
 
-        self.dirty = false;
-        debug!("Saved {} feeds to cache", self.feeds.len());
+    /// An empty cache that is never written to disk.
+    #[cfg(test)]
+    pub(crate) fn in_memory() -> Self {
+        Self {
+            feeds: HashMap::new(),
+            changes: Changes::default(),
+            read_only: true,
+        }
+    }
 
-        Ok(())
+    /// Keep changes in memory only, e.g. while another instance owns the cache.
+    pub fn set_read_only(&mut self) {
+        self.read_only = true;
     }
 
-    /// Get the cache file path.
-    fn cache_path() -> Result<PathBuf> {
+    /// Get the directory the cache lives in.
+    fn cache_dir() -> Result<PathBuf> {
         Config::data_dir()
-            .map(|dir| dir.join("cache.json"))
             .ok_or_else(|| color_eyre::eyre::eyre!("Could not determine cache directory"))
     }
 
+    /// Copy the cached data, e.g. to hand to a background task.
+    ///
+    /// The copy starts clean, so dropping it never writes to disk.
+    #[must_use]
+    pub fn snapshot(&self) -> Self {
+        Self {
+            feeds: self.feeds.clone(),
+            changes: Changes::default(),
+            read_only: false,
+        }
+    }
+
     /// Get cached feed by URL.
     #[must_use]
     pub fn get(&self, url: &str) -> Option<&CachedFeed> {
@@ -153,6 +326,10 @@ impl FeedCache {
     }
 
     /// Update cache for a feed.
+    ///
+    /// On success, `items` become the feed's current items. Items that are
+    /// no longer in the feed are kept, marked as archived, until
+    /// [`Self::prune`] drops them. On error the items are left alone.
     pub fn update_feed(
         &mut self,
         url: &str,
@@ -171,6 +348,16 @@ impl FeedCache {
                 items: Vec::new(),
                 last_fetched: None,
                 last_error: None,
+                failures: 0,
+                failing_since: None,
+                http_status: None,
+                moved_to: None,
+                moved_count: 0,
+                etag: None,
+                last_modified: None,
+                schedule: ScheduleHints::default(),
+                meta: FeedMeta::default(),
+                read_tombstones: HashMap::new(),
             });
 
         cached.name = name.to_string();
@@ -178,27 +365,146 @@ impl FeedCache {
 
         if cached.last_error.is_none() {
             cached.last_fetched = Some(now);
+            cached.failures = 0;
+            cached.failing_since = None;
 
-            // Merge items, preserving read state
-            let old_states: HashMap<String, bool> = cached
-                .items
+            // Match fresh items to cached ones by ID, or by link (or title)
+            // for entries cached under an older ID. Each cached item matches
+            // at most once, so distinct items sharing a link stay apart.
+            let old_items = std::mem::take(&mut cached.items);
+            let by_id: HashMap<&str, usize> = old_items
                 .iter()
-                .map(|i| (i.id.clone(), i.read))
+                .enumerate()
+                .map(|(n, i)| (i.id.as_str(), n))
                 .collect();
+            let mut legacy: HashMap<&str, Vec<usize>> = HashMap::new();
+            for (n, item) in old_items.iter().enumerate().rev() {
+                if is_legacy_id(&item.id) {
+                    legacy
+                        .entry(item.link.as_deref().unwrap_or(&item.title))
+                        .or_default()
+                        .push(n);
+                }
+            }
+            let mut matched = vec![false; old_items.len()];
 
             cached.items = items
                 .into_iter()
                 .map(|mut item| {
-                    // Restore read state from old cache
-                    if let Some(&was_read) = old_states.get(&item.id) {
-                        item.read = was_read;
+                    let old = by_id
+                        .get(item.id.as_str())
+                        .copied()
+                        .filter(|&n| !matched[n])
+                        .or_else(|| {
+                            let candidates =
+                                legacy.get_mut(item.link.as_deref().unwrap_or(&item.title))?;
+                            std::iter::from_fn(|| candidates.pop()).find(|&n| !matched[n])
+                        });
+                    // Restore read state from old cache, unless the item was
+                    // edited: then the caller has already worked it out
+                    if let Some(n) = old {
+                        matched[n] = true;
+                        let old = &old_items[n];
+                        let known = KnownItem {
+                            content_hash: old.content_hash.clone(),
+                            ..KnownItem::default()
+                        };
+                        if !known.is_edited(&item.content_hash) {
+                            item.read = old.read;
+                        }
+                        item.starred = old.starred;
+                        item.cached_at = old.cached_at;
+                    } else if cached.read_tombstones.contains_key(&item.id) {
+                        item.read = true;
+                    }
+                    // Back in the cache, so the item carries its own state again
+                    cached.read_tombstones.remove(&item.id);
+                    item.archived = false;
+                    if old.map(|n| &old_items[n]) != Some(&item) {
+                        self.changes.row(url, &item.id);
                     }
                     item
                 })
                 .collect();
+
+            // Keep items that dropped out of the feed as archive, newest first
+            let mut archived: Vec<CachedItem> = old_items
+                .into_iter()
+                .zip(matched)
+                .filter(|(_, matched)| !matched)
+                .map(|(mut i, _)| {
+                    if !i.archived {
+                        i.archived = true;
+                        self.changes.row(url, &i.id);
+                    }
+                    i
+                })
+                .collect();
+            archived.sort_by_key(|i| std::cmp::Reverse(i.published.unwrap_or(i.cached_at)));
+            cached.items.extend(archived);
+        } else {
+            cached.failures += 1;
+            cached.failing_since.get_or_insert(now);
         }
 
-        self.dirty = true;
+        self.changes.feed(url);
+    }
+
+    /// Record the HTTP cache validators returned with the latest fetch.
+    pub fn set_validators(
+        &mut self,
+        url: &str,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    ) {
+        if let Some(feed) = self.feeds.get_mut(url) {
+            if feed.etag != etag || feed.last_modified != last_modified {
+                feed.etag = etag;
+                feed.last_modified = last_modified;
+                self.changes.feed(url);
+            }
+        }
+    }
+
+    /// Record the refresh hints returned with the latest fetch.
+    pub fn set_schedule(&mut self, url: &str, schedule: ScheduleHints) {
+        if let Some(feed) = self.feeds.get_mut(url) {
+            if feed.schedule != schedule {
+                feed.schedule = schedule;
+                self.changes.feed(url);
+            }
+        }
+    }
+
+    /// Record the channel metadata returned with the latest fetch.
+    pub fn set_meta(&mut self, url: &str, meta: FeedMeta) {
+        if let Some(feed) = self.feeds.get_mut(url) {
+            if feed.meta != meta {
+                feed.meta = meta;
+                self.changes.feed(url);
+            }
+        }
+    }
+
+    /// Record a `304 Not Modified` response, keeping the cached items.
+    pub fn mark_not_modified(&mut self, url: &str) {
+        if let Some(feed) = self.feeds.get_mut(url) {
+            feed.last_fetched = Some(Utc::now());
+            feed.last_error = None;
+            feed.failures = 0;
+            feed.failing_since = None;
+            self.changes.feed(url);
+        }
+    }
+
+    /// Record the HTTP status of the latest response.
+    pub fn set_http_status(&mut self, url: &str, status: Option<u16>) {
+        if let Some(feed) = self.feeds.get_mut(url) {
+            if feed.http_status != status {
+                feed.http_status = status;
+                self.changes.feed(url);
+            }
+        }
     }
 
     /// Mark an item as read/unread.
@@ -207,7 +513,44 @@ impl FeedCache {
             if let Some(item) = feed.items.iter_mut().find(|i| i.id == item_id) {
                 if item.read != read {
                     item.read = read;
-                    self.dirty = true;
+                    self.changes.item(feed_url, item_id);
+                }
+            }
+        }
+    }
+
+    /// Flag or clear an item's text as changed since it was read.
+    ///
+    /// Clearing the flag also drops the text from before the change.
+    pub fn set_item_changed(&mut self, feed_url: &str, item_id: &str, changed: bool) {
+        if let Some(feed) = self.feeds.get_mut(feed_url) {
+            if let Some(item) = feed.items.iter_mut().find(|i| i.id == item_id) {
+                if item.changed != changed {
+                    item.changed = changed;
+                    self.changes.item(feed_url, item_id);
+                }
+                if !changed && item.previous_body.take().is_some() {
+                    self.changes.row(feed_url, item_id);
+                }
+            }
+        }
+    }
+
+    /// Whether an item that is no longer cached was read before it was pruned.
+    #[must_use]
+    pub fn was_read(&self, feed_url: &str, item_id: &str) -> bool {
+        self.feeds
+            .get(feed_url)
+            .is_some_and(|f| f.read_tombstones.contains_key(item_id))
+    }
+
+    /// Star or unstar an item.
+    pub fn set_item_starred(&mut self, feed_url: &str, item_id: &str, starred: bool) {
+        if let Some(feed) = self.feeds.get_mut(feed_url) {
+            if let Some(item) = feed.items.iter_mut().find(|i| i.id == item_id) {
+                if item.starred != starred {
+                    item.starred = starred;
+                    self.changes.item(feed_url, item_id);
                 }
             }
         }
@@ -217,21 +560,82 @@ impl FeedCache {
     pub fn mark_feed_read(&mut self, feed_url: &str) {
         if let Some(feed) = self.feeds.get_mut(feed_url) {
             for item in &mut feed.items {
-                if !item.read {
+                if !item.read || item.changed {
                     item.read = true;
-                    self.dirty = true;
+                    item.changed = false;
+                    self.changes.item(feed_url, &item.id);
+                }
+                if item.previous_body.take().is_some() {
+                    self.changes.row(feed_url, &item.id);
                 }
             }
         }
     }
 
+    /// Record where a successful fetch was permanently redirected to.
+    ///
+    /// Returns how many fetches in a row have been redirected to the same
+    /// place, or 0 if this one wasn't.
+    pub fn record_redirect(&mut self, url: &str, moved_to: Option<&str>) -> u32 {
+        let Some(feed) = self.feeds.get_mut(url) else {
+            return 0;
+        };
+
+        let count = match moved_to {
+            Some(to) if feed.moved_to.as_deref() == Some(to) => feed.moved_count + 1,
+            Some(_) => 1,
+            None => 0,
+        };
+
+        if feed.moved_count != count || feed.moved_to.as_deref() != moved_to {
+            feed.moved_to = moved_to.map(ToString::to_string);
+            feed.moved_count = count;
+            self.changes.feed(url);
+        }
+        count
+    }
+
+    /// Move a feed's cached data, including read states, to a new URL.
+    pub fn rename_feed(&mut self, from: &str, to: &str) {
+        if let Some(mut feed) = self.feeds.remove(from) {
+            feed.url = to.to_string();
+            feed.moved_to = None;
+            feed.moved_count = 0;
+            self.feeds.insert(to.to_string(), feed);
+            self.changes.remove(from);
+            self.changes.feed(to);
+        }
+    }
+
     /// Remove a feed from cache.
     pub fn remove_feed(&mut self, url: &str) {
         if self.feeds.remove(url).is_some() {
-            self.dirty = true;
+            self.changes.remove(url);
         }
     }
 
+    /// All cached feeds, in no particular order.
+    pub fn feeds(&self) -> impl Iterator<Item = &CachedFeed> {
+        self.feeds.values()
+    }
+
+    /// Put a feed from a backup into the cache.
+    ///
+    /// Without `merge` the backup replaces the cached copy. With it, items
+    /// from both are kept, and an item is read or starred if it is in either;
+    /// the cached copy's fetch state wins.
+    pub fn restore_feed(&mut self, feed: CachedFeed, merge: bool) {
+        let feed = match self.feeds.remove(&feed.url) {
+            Some(current) if merge => merge_feeds(current, feed),
+            _ => feed,
+        };
+        self.changes.feed(&feed.url);
+        for item in &feed.items {
+            self.changes.row(&feed.url, &item.id);
+        }
+        self.feeds.insert(feed.url.clone(), feed);
+    }
+
     /// Get cache statistics.
     #[must_use]
     pub fn stats(&self) -> CacheStats {
@@ -254,34 +658,90 @@ impl FeedCache {
         }
     }
 
-    /// Prune old items beyond a limit per feed.
-    pub fn prune(&mut self, max_items_per_feed: usize) {
-        for feed in self.feeds.values_mut() {
-            if feed.items.len() > max_items_per_feed {
-                // Keep newest items, but always keep unread items
-                feed.items.sort_by(|a, b| {
-                    // Unread items first, then by date descending
-                    match (a.read, b.read) {
-                        (false, true) => std::cmp::Ordering::Less,
-                        (true, false) => std::cmp::Ordering::Greater,
-                        _ => b.cached_at.cmp(&a.cached_at),
-                    }
-                });
+    /// Drop archived items of a feed as the retention policy says.
+    ///
+    /// Items still in the feed are never dropped: they would only come back
+    /// as new on the next fetch. Of the rest, those the policy doesn't keep
+    /// go once they are too old, then oldest first while the feed has too
+    /// many. Read items that go leave a tombstone behind, so they stay read
+    /// if they come back. Returns how many were dropped.
+    pub fn prune(&mut self, url: &str, retention: &Retention) -> usize {
+        let Some(feed) = self.feeds.get_mut(url) else {
+            return 0;
+        };
 
-                let old_len = feed.items.len();
-                feed.items.truncate(max_items_per_feed);
+        let mut candidates: Vec<usize> = feed
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, i)| i.archived && !retention.keeps(i.read, i.starred))
+            .map(|(index, _)| index)
+            .collect();
+        candidates.sort_by_key(|&index| feed.items[index].cached_at);
 
-                if feed.items.len() < old_len {
-                    self.dirty = true;
-                    debug!(
-                        "Pruned {} items from {}",
-                        old_len - feed.items.len(),
-                        feed.name
-                    );
-                }
+        let now = Utc::now();
+        let cutoff = retention.cutoff(now);
+        let mut excess = retention
+            .max_items()
+            .map_or(0, |max| feed.items.len().saturating_sub(max));
+        let mut keep = vec![true; feed.items.len()];
+        for index in candidates {
+            let expired = cutoff.is_some_and(|cutoff| feed.items[index].cached_at < cutoff);
+            if !expired && excess == 0 {
+                break;
+            }
+            keep[index] = false;
+            excess = excess.saturating_sub(1);
+        }
+
+        let old_len = feed.items.len();
+        let mut keep = keep.into_iter();
+        let tombstones = &mut feed.read_tombstones;
+        feed.items.retain(|item| {
+            let kept = keep.next().unwrap_or(true);
+            if !kept && item.read {
+                tombstones.insert(item.id.clone(), now);
+            }
+            kept
+        });
+
+        let expired = now - chrono::Duration::days(READ_TOMBSTONE_DAYS);
+        let tombstone_count = feed.read_tombstones.len();
+        feed.read_tombstones
+            .retain(|_, &mut pruned_at| pruned_at > expired);
+
+        let pruned = old_len - feed.items.len();
+        if pruned > 0 || feed.read_tombstones.len() != tombstone_count {
+            self.changes.feed(url);
+        }
+        if pruned > 0 {
+            debug!("Pruned {pruned} items from {}", feed.name);
+        }
+        pruned
+    }
+}
+
+/// Combine the cached copy of a feed with one from a backup.
+fn merge_feeds(mut current: CachedFeed, backup: CachedFeed) -> CachedFeed {
+    for item in backup.items {
+        match current.items.iter_mut().find(|i| i.id == item.id) {
+            Some(existing) => {
+                existing.read |= item.read;
+                existing.starred |= item.starred;
             }
+            // Older articles go after the ones in the feed
+            None => current.items.push(CachedItem {
+                archived: true,
+                ..item
+            }),
         }
     }
+
+    for (id, pruned_at) in backup.read_tombstones {
+        let at = current.read_tombstones.entry(id).or_insert(pruned_at);
+        *at = (*at).max(pruned_at);
+    }
+    current
 }
 
 /// Cache statistics.
@@ -299,7 +759,7 @@ pub struct CacheStats {
 
 impl Drop for FeedCache {
     fn drop(&mut self) {
-        if self.dirty {
+        if !self.changes.is_empty() {
             if let Err(e) = self.save() {
                 warn!("Failed to save cache on drop: {e}");
             }
@@ -313,17 +773,294 @@ mod tests {
 
     #[test]
     fn test_generate_id() {
-        let id1 = CachedItem::generate_id(Some("https://example.com/1"), "Title");
-        let id2 = CachedItem::generate_id(Some("https://example.com/2"), "Title");
-        let id3 = CachedItem::generate_id(None, "Title");
+        let id1 = CachedItem::generate_id(None, Some("https://example.com/1"), "Title");
+        let id2 = CachedItem::generate_id(None, Some("https://example.com/2"), "Title");
+        let id3 = CachedItem::generate_id(None, None, "Title");
 
         assert_ne!(id1, id2);
         assert_ne!(id1, id3);
     }
 
+    #[test]
+    fn test_validators_survive_item_updates() {
+        let mut cache = FeedCache::in_memory();
+        let url = "https://example.com/feed.xml";
+
+        cache.update_feed(url, "Example", Vec::new(), None);
+        cache.set_validators(url, Some("\"abc\"".to_string()), None);
+        cache.update_feed(url, "Example", Vec::new(), None);
+
+        let feed = cache.get(url).unwrap();
+        assert_eq!(feed.etag.as_deref(), Some("\"abc\""));
+        assert!(feed.last_modified.is_none());
+    }
+
+    #[test]
+    fn test_failures_reset_on_success() {
+        let mut cache = FeedCache::in_memory();
+        let url = "https://example.com/feed.xml";
+
+        cache.update_feed(url, "Example", Vec::new(), Some("HTTP 500".to_string()));
+        cache.update_feed(url, "Example", Vec::new(), Some("HTTP 500".to_string()));
+        let feed = cache.get(url).unwrap();
+        assert_eq!(feed.failures, 2);
+        let since = feed.failing_since;
+        assert!(since.is_some());
+
+        // The run of failures keeps its start time
+        cache.update_feed(url, "Example", Vec::new(), Some("HTTP 502".to_string()));
+        assert_eq!(cache.get(url).unwrap().failing_since, since);
+
+        cache.mark_not_modified(url);
+        let feed = cache.get(url).unwrap();
+        assert_eq!(feed.failures, 0);
+        assert!(feed.failing_since.is_none());
+        assert!(feed.last_fetched.is_some());
+    }
+
+    #[test]
+    fn test_redirects_then_rename() {
+        let mut cache = FeedCache::in_memory();
+        let old = "http://example.com/feed.xml";
+        let new = "https://example.com/feed.xml";
+
+        cache.update_feed(old, "Example", Vec::new(), None);
+        assert_eq!(cache.record_redirect(old, Some(new)), 1);
+        assert_eq!(cache.record_redirect(old, Some(new)), 2);
+        assert_eq!(cache.record_redirect(old, None), 0);
+        assert_eq!(cache.record_redirect(old, Some(new)), 1);
+
+        cache.set_validators(old, Some("\"abc\"".to_string()), None);
+        cache.rename_feed(old, new);
+        assert!(cache.get(old).is_none());
+        let feed = cache.get(new).unwrap();
+        assert_eq!(feed.url, new);
+        assert_eq!(feed.etag.as_deref(), Some("\"abc\""));
+        assert!(feed.moved_to.is_none());
+    }
+
+    fn item(title: &str, read: bool) -> CachedItem {
+        CachedItem {
+            id: CachedItem::generate_id(None, None, title),
+            title: title.to_string(),
+            link: None,
+            alternate_links: Vec::new(),
+            published: None,
+            updated: None,
+            authors: Vec::new(),
+            categories: Vec::new(),
+            summary: None,
+            content: None,
+            enclosures: Vec::new(),
+            content_hash: String::new(),
+            changed: false,
+            previous_body: None,
+            archived: false,
+            starred: false,
+            read,
+            cached_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_dropped_items_are_archived() {
+        let mut cache = FeedCache::in_memory();
+        let url = "https://example.com/feed.xml";
+
+        cache.update_feed(
+            url,
+            "Example",
+            vec![item("One", false), item("Two", true)],
+            None,
+        );
+        cache.update_feed(
+            url,
+            "Example",
+            vec![item("Two", false), item("Three", false)],
+            None,
+        );
+
+        let items = &cache.get(url).unwrap().items;
+        let titles: Vec<(&str, bool)> = items
+            .iter()
+            .map(|i| (i.title.as_str(), i.archived))
+            .collect();
+        assert_eq!(titles, [("Two", false), ("Three", false), ("One", true)]);
+
+        // Read state carries over, and the unread item we never got to is still there
+        assert!(items[0].read);
+        assert!(!items[2].read);
+
+        // Refetched items keep their first-seen time
+        let first_seen = items[0].cached_at;
+        cache.update_feed(url, "Example", vec![item("Two", false)], None);
+        assert_eq!(cache.get(url).unwrap().items[0].cached_at, first_seen);
+    }
+
+    #[test]
+    fn test_items_sharing_a_link_stay_apart() {
+        let mut cache = FeedCache::in_memory();
+        let url = "https://example.com/feed.xml";
+        let linked = |title: &str, id: &str| CachedItem {
+            id: id.to_string(),
+            link: Some("https://example.com/live".to_string()),
+            ..item(title, true)
+        };
+
+        // Distinct current IDs: the one that drops out is archived
+        let (morning, evening) = (
+            CachedItem::generate_id(Some("morning"), None, ""),
+            CachedItem::generate_id(Some("evening"), None, ""),
+        );
+        cache.update_feed(
+            url,
+            "Example",
+            vec![linked("Morning", &morning), linked("Evening", &evening)],
+            None,
+        );
+        let mut fresh = linked("Evening", &evening);
+        fresh.read = false;
+        cache.update_feed(url, "Example", vec![fresh], None);
+        let items = &cache.get(url).unwrap().items;
+        let titles: Vec<(&str, bool, bool)> = items
+            .iter()
+            .map(|i| (i.title.as_str(), i.read, i.archived))
+            .collect();
+        assert_eq!(titles, [("Evening", true, false), ("Morning", true, true)]);
+
+        // Legacy IDs migrate by link, one cached item per fresh one
+        let feed = cache.feeds.get_mut(url).unwrap();
+        feed.items = vec![linked("Morning", "1a2b"), linked("Evening", "3c4d")];
+        let mut fresh = linked("Evening", &evening);
+        fresh.read = false;
+        cache.update_feed(url, "Example", vec![fresh], None);
+        let items = &cache.get(url).unwrap().items;
+        assert_eq!(items.len(), 2);
+        assert!(items[0].read && !items[0].archived);
+        assert_eq!(items[1].id, "3c4d");
+        assert!(items[1].archived);
+    }
+
+    #[test]
+    fn test_prune_archive() {
+        let mut cache = FeedCache::in_memory();
+        let url = "https://example.com/feed.xml";
+        let days_ago = |days, mut item: CachedItem| {
+            item.archived = true;
+            item.cached_at = Utc::now() - chrono::Duration::days(days);
+            item
+        };
+
+        let mut starred = days_ago(90, item("Starred", true));
+        starred.starred = true;
+        cache.update_feed(url, "Example", Vec::new(), None);
+        cache.feeds.get_mut(url).unwrap().items = vec![
+            item("Current", true),
+            days_ago(1, item("New", true)),
+            days_ago(90, item("Unread", false)),
+            days_ago(40, item("Old", true)),
+            starred,
+            days_ago(60, item("Older", true)),
+        ];
+
+        // Too many: the oldest read one goes
+        let retention = Retention {
+            max_items: Some(5),
+            ..Retention::default()
+        };
+        assert_eq!(cache.prune(url, &retention), 1);
+
+        // Too old: everything unprotected past 30 days goes
+        let retention = Retention {
+            max_age_days: Some(30),
+            ..Retention::default()
+        };
+        assert_eq!(cache.prune(url, &retention), 1);
+
+        let titles: Vec<&str> = cache
+            .get(url)
+            .unwrap()
+            .items
+            .iter()
+            .map(|i| i.title.as_str())
+            .collect();
+        assert_eq!(titles, ["Current", "New", "Unread", "Starred"]);
+
+        // Unless unread and starred items are fair game too
+        let retention = Retention {
+            max_items: Some(1),
+            keep_unread: Some(false),
+            keep_starred: Some(false),
+            ..Retention::default()
+        };
+        assert_eq!(cache.prune(url, &retention), 3);
+        assert_eq!(cache.get(url).unwrap().items[0].title, "Current");
+    }
+
+    #[test]
+    fn test_pruned_read_items_stay_read() {
+        let mut cache = FeedCache::in_memory();
+        let url = "https://example.com/feed.xml";
+        let retention = Retention {
+            max_items: Some(1),
+            ..Retention::default()
+        };
+
+        cache.update_feed(url, "Example", vec![item("Old", true)], None);
+        cache.update_feed(url, "Example", vec![item("New", false)], None);
+        assert_eq!(cache.prune(url, &retention), 1);
+        let old_id = CachedItem::generate_id(None, None, "Old");
+        assert!(cache.was_read(url, &old_id));
+
+        // The aggregator brings it back
+        cache.update_feed(
+            url,
+            "Example",
+            vec![item("Old", false), item("New", false)],
+            None,
+        );
+        let feed = cache.get(url).unwrap();
+        assert!(feed.items[0].read);
+        assert!(!feed.items[1].read);
+        assert!(feed.read_tombstones.is_empty());
+    }
+
+    #[test]
+    fn test_restore_feed_merges_state() {
+        let mut cache = FeedCache::in_memory();
+        let url = "https://example.com/feed.xml";
+        cache.update_feed(url, "Example", vec![item("One", false)], None);
+
+        let mut backup = cache.get(url).unwrap().clone();
+        backup.items = vec![
+            CachedItem {
+                starred: true,
+                ..item("One", true)
+            },
+            item("Gone", true),
+        ];
+        backup.etag = Some("\"old\"".to_string());
+
+        cache.restore_feed(backup.clone(), true);
+        let feed = cache.get(url).unwrap();
+        let state: Vec<(&str, bool, bool, bool)> = feed
+            .items
+            .iter()
+            .map(|i| (i.title.as_str(), i.read, i.starred, i.archived))
+            .collect();
+        assert_eq!(
+            state,
+            [("One", true, true, false), ("Gone", true, false, true)]
+        );
+        assert!(feed.etag.is_none());
+
+        cache.restore_feed(backup, false);
+        assert_eq!(cache.get(url).unwrap().etag.as_deref(), Some("\"old\""));
+    }
+
     #[test]
     fn test_cache_stats() {
-        let cache = FeedCache::default();
+        let cache = FeedCache::in_memory();
         let stats = cache.stats();
 
         assert_eq!(stats.total_feeds, 0);
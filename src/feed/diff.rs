@@ -0,0 +1,148 @@
+//! Word-level diffs between two versions of an article.
+
+/// A run of words in a diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffSpan {
+    /// Words in both versions.
+    Same(String),
+    /// Words only in the new version.
+    Added(String),
+    /// Words only in the old version.
+    Removed(String),
+}
+
+/// Most word pairs compared. Beyond that, the changed middle of the text is
+/// shown as removed and re-added as a whole.
+const MAX_CELLS: usize = 4_000_000;
+
+/// Diff two texts word by word, ignoring differences in whitespace.
+#[must_use]
+pub fn word_diff(old: &str, new: &str) -> Vec<DiffSpan> {
+    let old: Vec<&str> = old.split_whitespace().collect();
+    let new: Vec<&str> = new.split_whitespace().collect();
+
+    // Edits are usually small; only diff what lies between the common ends
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let mut spans = Vec::new();
+    for word in &old[..prefix] {
+        push(&mut spans, DiffSpan::Same, word);
+    }
+
+    if a.len().saturating_mul(b.len()) > MAX_CELLS {
+        for word in a {
+            push(&mut spans, DiffSpan::Removed, word);
+        }
+        for word in b {
+            push(&mut spans, DiffSpan::Added, word);
+        }
+    } else {
+        diff_middle(a, b, &mut spans);
+    }
+
+    for word in &old[old.len() - suffix..] {
+        push(&mut spans, DiffSpan::Same, word);
+    }
+    spans
+}
+
+/// Diff two word lists via their longest common subsequence.
+fn diff_middle(a: &[&str], b: &[&str], spans: &mut Vec<DiffSpan>) {
+    let width = b.len() + 1;
+    // lcs[i * width + j] is the LCS length of a[i..] and b[j..]
+    let mut lcs = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i * width + j] = if a[i] == b[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            push(spans, DiffSpan::Same, a[i]);
+            i += 1;
+            j += 1;
+        } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+            push(spans, DiffSpan::Removed, a[i]);
+            i += 1;
+        } else {
+            push(spans, DiffSpan::Added, b[j]);
+            j += 1;
+        }
+    }
+    for word in &a[i..] {
+        push(spans, DiffSpan::Removed, word);
+    }
+    for word in &b[j..] {
+        push(spans, DiffSpan::Added, word);
+    }
+}
+
+/// Append a word, merging it into the last span if that is of the same kind.
+fn push(spans: &mut Vec<DiffSpan>, kind: fn(String) -> DiffSpan, word: &str) {
+    let next = kind(String::new());
+    if let Some(last) = spans.last_mut() {
+        let text = match (last, &next) {
+            (DiffSpan::Same(t), DiffSpan::Same(_))
+            | (DiffSpan::Added(t), DiffSpan::Added(_))
+            | (DiffSpan::Removed(t), DiffSpan::Removed(_)) => Some(t),
+            _ => None,
+        };
+        if let Some(text) = text {
+            text.push(' ');
+            text.push_str(word);
+            return;
+        }
+    }
+    spans.push(kind(word.to_string()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_diff() {
+        let spans = word_diff(
+            "The quick brown fox jumps over the dog",
+            "The quick red fox jumps over the  lazy dog",
+        );
+        assert_eq!(
+            spans,
+            vec![
+                DiffSpan::Same("The quick".to_string()),
+                DiffSpan::Removed("brown".to_string()),
+                DiffSpan::Added("red".to_string()),
+                DiffSpan::Same("fox jumps over the".to_string()),
+                DiffSpan::Added("lazy".to_string()),
+                DiffSpan::Same("dog".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_word_diff_identical_and_empty() {
+        assert_eq!(
+            word_diff("a  b\nc", "a b c"),
+            vec![DiffSpan::Same("a b c".to_string())]
+        );
+        assert_eq!(
+            word_diff("", "new"),
+            vec![DiffSpan::Added("new".to_string())]
+        );
+        assert!(word_diff("", "").is_empty());
+    }
+}
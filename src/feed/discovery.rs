@@ -9,6 +9,9 @@ use color_eyre::{Result, eyre::eyre};
 use regex_lite::Regex;
 use tracing::debug;
 
+use super::FeedMeta;
+use crate::http::{self, ClientSettings, RequestOptions};
+
 /// Discovered feed information.
 #[derive(Debug, Clone)]
 pub struct DiscoveredFeed {
@@ -18,6 +21,8 @@ pub struct DiscoveredFeed {
     pub title: Option<String>,
     /// Feed type (RSS, Atom, etc.).
     pub feed_type: FeedType,
+    /// Channel metadata (description, website, ...).
+    pub meta: FeedMeta,
 }
 
 /// Type of feed discovered.
@@ -47,6 +52,7 @@ impl std::fmt::Display for FeedType {
 /// Feed discovery client.
 pub struct FeedDiscovery {
     client: reqwest::Client,
+    options: RequestOptions,
 }
 
 impl FeedDiscovery {
@@ -54,15 +60,36 @@ impl FeedDiscovery {
     ///
     /// # Errors
     ///
-    /// Returns an error if the HTTP client cannot be created.
-    pub fn new() -> Result<Self> {
-        let client = reqwest::Client::builder()
-            .user_agent(concat!("feedo/", env!("CARGO_PKG_VERSION")))
+    /// Returns an error if the HTTP client cannot be created, e.g. because
+    /// the proxy URL is invalid.
+    pub fn new(settings: &ClientSettings) -> Result<Self> {
+        let client = http::client_builder(settings)?
             .timeout(std::time::Duration::from_secs(15))
             .redirect(reqwest::redirect::Policy::limited(5))
             .build()?;
 
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            options: RequestOptions::default(),
+        })
+    }
+
+    /// Use per-feed HTTP settings, such as credentials, for discovery requests.
+    ///
+    /// Credentials and extra headers are only sent to the host being discovered.
+    #[must_use]
+    pub fn with_options(mut self, options: RequestOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Build a GET request with the configured settings.
+    fn get(&self, url: &str, origin: &str) -> reqwest::RequestBuilder {
+        let same_origin = match (reqwest::Url::parse(origin), reqwest::Url::parse(url)) {
+            (Ok(origin), Ok(url)) => http::same_origin(&origin, &url),
+            _ => false,
+        };
+        self.options.apply(self.client.get(url), same_origin)
     }
 
     /// Discover feeds from a URL.
@@ -82,14 +109,14 @@ impl FeedDiscovery {
         let mut feeds = Vec::new();
 
         // Try direct URL first
-        if let Ok(feed) = self.try_as_feed(&url).await {
+        if let Ok(feed) = self.try_as_feed(&url, &url).await {
             debug!("URL is a direct feed");
             feeds.push(feed);
             return Ok(feeds);
         }
 
         // Fetch the page and look for feed links
-        let response = self.client.get(&url).send().await?;
+        let response = self.get(&url, &url).send().await?;
         let content_type = response
             .headers()
             .get(reqwest::header::CONTENT_TYPE)
@@ -102,7 +129,7 @@ impl FeedDiscovery {
 
             // Validate each discovered URL
             for feed_url in discovered {
-                if let Ok(feed) = self.try_as_feed(&feed_url).await {
+                if let Ok(feed) = self.try_as_feed(&feed_url, &url).await {
                     feeds.push(feed);
                 }
             }
@@ -128,7 +155,7 @@ impl FeedDiscovery {
 
             for path in common_paths {
                 let test_url = format!("{base_url}{path}");
-                if let Ok(feed) = self.try_as_feed(&test_url).await {
+                if let Ok(feed) = self.try_as_feed(&test_url, &url).await {
                     feeds.push(feed);
                     break; // Found one, good enough
                 }
@@ -143,8 +170,10 @@ impl FeedDiscovery {
     }
 
     /// Try to parse a URL as a feed directly.
-    async fn try_as_feed(&self, url: &str) -> Result<DiscoveredFeed> {
-        let response = self.client.get(url).send().await?;
+    ///
+    /// `origin` is the URL discovery started from.
+    async fn try_as_feed(&self, url: &str, origin: &str) -> Result<DiscoveredFeed> {
+        let response = self.get(url, origin).send().await?;
 
         if !response.status().is_success() {
             return Err(eyre!("HTTP {}", response.status()));
@@ -180,10 +209,12 @@ impl FeedDiscovery {
             FeedType::Unknown
         };
 
+        let meta = FeedMeta::from_feed(&feed);
         Ok(DiscoveredFeed {
             url: url.to_string(),
-            title: feed.title.map(|t| t.content),
+            title: meta.title.clone(),
             feed_type,
+            meta,
         })
     }
 
@@ -0,0 +1,151 @@
+//! Feed health tracking.
+//!
+//! Failing feeds are retried with exponential backoff. A feed that has
+//! failed for long enough is considered dead and no longer refreshed
+//! automatically, until a manual refresh succeeds.
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::CachedFeed;
+
+/// Delay before the first retry of a failing feed, in minutes.
+const RETRY_BASE: i64 = 5;
+
+/// Longest delay between retries, in minutes.
+const RETRY_MAX: i64 = 24 * 60;
+
+/// Overall health of a feed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HealthStatus {
+    /// Failing for longer than the configured limit; no longer auto-refreshed.
+    Dead,
+    /// The last fetch failed.
+    Failing,
+    /// The last fetch succeeded.
+    Healthy,
+}
+
+impl HealthStatus {
+    /// Icon shown next to the feed.
+    #[must_use]
+    pub const fn icon(self) -> &'static str {
+        match self {
+            Self::Dead => "💀",
+            Self::Failing => "⚠",
+            Self::Healthy => "✓",
+        }
+    }
+}
+
+/// Health summary for a single feed.
+#[derive(Debug, Clone)]
+pub struct FeedHealth {
+    /// Overall status.
+    pub status: HealthStatus,
+
+    /// Number of fetches that have failed in a row.
+    pub failures: u32,
+
+    /// When the current run of failures started.
+    pub failing_since: Option<DateTime<Utc>>,
+
+    /// Last successful fetch.
+    pub last_success: Option<DateTime<Utc>>,
+
+    /// HTTP status of the last response, if the server answered.
+    pub http_status: Option<u16>,
+
+    /// Last error message.
+    pub error: Option<String>,
+}
+
+impl FeedHealth {
+    /// Summarize the health of a cached feed.
+    ///
+    /// `dead_after_days` of 0 means feeds are never considered dead.
+    #[must_use]
+    pub fn of(cached: &CachedFeed, dead_after_days: u32, now: DateTime<Utc>) -> Self {
+        let status = match cached.failing_since {
+            Some(since)
+                if dead_after_days > 0
+                    && now - since >= Duration::days(i64::from(dead_after_days)) =>
+            {
+                HealthStatus::Dead
+            }
+            _ if cached.failures > 0 => HealthStatus::Failing,
+            _ => HealthStatus::Healthy,
+        };
+
+        Self {
+            status,
+            failures: cached.failures,
+            failing_since: cached.failing_since,
+            last_success: cached.last_fetched,
+            http_status: cached.http_status,
+            error: cached.last_error.clone(),
+        }
+    }
+}
+
+/// How long to wait before retrying a feed after `failures` failures in a row.
+#[must_use]
+pub fn retry_delay(failures: u32) -> Duration {
+    let doublings = failures.saturating_sub(1).min(16);
+    Duration::minutes((RETRY_BASE << doublings).min(RETRY_MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::feed::{FeedMeta, ScheduleHints};
+
+    fn cached(failures: u32, failing_since: Option<DateTime<Utc>>) -> CachedFeed {
+        CachedFeed {
+            url: "https://example.com/feed.xml".to_string(),
+            name: "Example".to_string(),
+            items: Vec::new(),
+            last_fetched: None,
+            last_error: None,
+            failures,
+            failing_since,
+            http_status: None,
+            moved_to: None,
+            moved_count: 0,
+            etag: None,
+            last_modified: None,
+            schedule: ScheduleHints::default(),
+            meta: FeedMeta::default(),
+            read_tombstones: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        assert_eq!(retry_delay(1), Duration::minutes(5));
+        assert_eq!(retry_delay(2), Duration::minutes(10));
+        assert_eq!(retry_delay(4), Duration::minutes(40));
+        assert_eq!(retry_delay(100), Duration::minutes(RETRY_MAX));
+    }
+
+    #[test]
+    fn test_status() {
+        let now = Utc::now();
+
+        assert_eq!(
+            FeedHealth::of(&cached(0, None), 7, now).status,
+            HealthStatus::Healthy
+        );
+
+        let recent = cached(3, Some(now - Duration::days(2)));
+        assert_eq!(
+            FeedHealth::of(&recent, 7, now).status,
+            HealthStatus::Failing
+        );
+
+        let old = cached(40, Some(now - Duration::days(8)));
+        assert_eq!(FeedHealth::of(&old, 7, now).status, HealthStatus::Dead);
+        assert_eq!(FeedHealth::of(&old, 0, now).status, HealthStatus::Failing);
+    }
+}
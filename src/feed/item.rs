@@ -1,9 +1,17 @@
 //! Feed item data structure.
 
+use std::{collections::HashMap, fmt::Write};
+
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Length of item IDs, in hex digits. IDs of any other length predate
+/// stable hashing and are migrated on the next fetch.
+const ID_LEN: usize = 32;
 
 /// A single item/article from a feed.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct FeedItem {
     /// Unique ID (for cache matching).
     pub id: String,
@@ -14,63 +22,204 @@ pub struct FeedItem {
     /// Article URL (if available).
     pub link: Option<String>,
 
-    /// Publication date (if available).
+    /// Other links, e.g. comments or translations.
+    pub alternate_links: Vec<String>,
+
+    /// Publication date, or the update date if there is none.
     pub published: Option<DateTime<Utc>>,
 
-    /// Summary or content (if available).
+    /// Last update date (if available).
+    pub updated: Option<DateTime<Utc>>,
+
+    /// Author names.
+    pub authors: Vec<String>,
+
+    /// Category or tag names.
+    pub categories: Vec<String>,
+
+    /// Summary (if available).
     pub summary: Option<String>,
 
+    /// Full content (if available).
+    pub content: Option<String>,
+
+    /// Attached media, e.g. podcast episodes.
+    pub enclosures: Vec<Enclosure>,
+
+    /// Hash of the title and text, to notice when the publisher edits the item.
+    pub content_hash: String,
+
+    /// Whether the text changed after the item was read.
+    pub changed: bool,
+
+    /// The text as it was read, before the latest change.
+    pub previous_body: Option<String>,
+
+    /// Whether the item has dropped out of the feed and is only kept in the archive.
+    pub archived: bool,
+
+    /// Whether the item is starred, which protects it from pruning.
+    pub starred: bool,
+
     /// Whether the item has been read.
     pub read: bool,
 }
 
+/// A media file attached to an item (RSS `<enclosure>`, Atom `rel="enclosure"`, Media RSS).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Enclosure {
+    /// File URL.
+    pub url: String,
+
+    /// MIME type, e.g. `audio/mpeg`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+
+    /// Size in bytes, as announced by the feed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub length: Option<u64>,
+
+    /// Duration in seconds, as announced by the feed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u64>,
+}
+
+impl Enclosure {
+    /// Icon for the kind of media.
+    #[must_use]
+    pub fn icon(&self) -> &'static str {
+        match self.mime_type.as_deref().and_then(|m| m.split('/').next()) {
+            Some("audio") => "🎧",
+            Some("video") => "🎬",
+            Some("image") => "🖼",
+            _ => "📎",
+        }
+    }
+
+    /// File name taken from the URL, safe to use on disk.
+    #[must_use]
+    pub fn file_name(&self) -> String {
+        let last = reqwest::Url::parse(&self.url)
+            .ok()
+            .and_then(|u| {
+                u.path_segments()
+                    .and_then(|mut s| s.rfind(|s| !s.is_empty()).map(ToString::to_string))
+            })
+            .unwrap_or_default();
+        let decoded = urlencoding::decode(&last).map_or_else(|_| last.clone(), |d| d.into_owned());
+
+        let name = sanitize_file_name(&decoded);
+        if name.is_empty() {
+            "download".to_string()
+        } else {
+            name
+        }
+    }
+
+    /// Name to save the file under: [`Self::file_name`] with a hash of the
+    /// URL, since hosts often serve every episode as e.g. `media.mp3`.
+    #[must_use]
+    pub fn download_name(&self) -> String {
+        let name = self.file_name();
+        let hash = &short_hash(self.url.as_bytes())[..8];
+        match name.rsplit_once('.') {
+            Some((stem, ext)) if !stem.is_empty() => format!("{stem}-{hash}.{ext}"),
+            _ => format!("{name}-{hash}"),
+        }
+    }
+}
+
+/// Replace characters that aren't allowed in file names.
+#[must_use]
+pub fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim_matches(['.', ' '])
+        .to_string()
+}
+
 impl FeedItem {
     /// Create a new feed item with auto-generated ID.
     #[must_use]
     pub fn new(title: String) -> Self {
-        let id = Self::generate_id(None, &title);
+        let id = Self::generate_id(None, None, &title);
         Self {
             id,
             title,
-            link: None,
-            published: None,
-            summary: None,
-            read: false,
+            ..Default::default()
         }
     }
 
     /// Create a new feed item with link (uses link for ID).
     #[must_use]
     pub fn with_link(title: String, link: Option<String>) -> Self {
-        let id = Self::generate_id(link.as_deref(), &title);
+        let id = Self::generate_id(None, link.as_deref(), &title);
         Self {
             id,
             title,
             link,
-            published: None,
-            summary: None,
-            read: false,
+            ..Default::default()
         }
     }
 
-    /// Generate a unique ID for an item.
+    /// Generate a stable ID for an item.
+    ///
+    /// Uses the feed's own `<guid>`/Atom `<id>` if there is one, then the
+    /// link, then the title. The hash is SHA-256, so IDs are the same across
+    /// builds and platforms.
     #[must_use]
-    pub fn generate_id(link: Option<&str>, title: &str) -> String {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
+    pub fn generate_id(guid: Option<&str>, link: Option<&str>, title: &str) -> String {
+        let key = guid
+            .filter(|g| !g.trim().is_empty())
+            .or(link)
+            .unwrap_or(title);
 
-        let mut hasher = DefaultHasher::new();
-        if let Some(link) = link {
-            link.hash(&mut hasher);
-        } else {
-            title.hash(&mut hasher);
-        }
-        format!("{:x}", hasher.finish())
+        short_hash(key.as_bytes())
     }
 
-    /// Set the item as read.
-    pub const fn mark_read(&mut self) {
+    /// Hash the parts of an item that make up its content.
+    ///
+    /// Dates and links are left out, so only an actual edit of the text
+    /// changes the hash.
+    #[must_use]
+    pub fn hash_content(title: &str, body: Option<&str>) -> String {
+        let mut bytes = title.as_bytes().to_vec();
+        bytes.push(0);
+        bytes.extend_from_slice(body.unwrap_or_default().as_bytes());
+        short_hash(&bytes)
+    }
+
+    /// The text to show: the full content if there is any, otherwise the summary.
+    #[must_use]
+    pub fn body(&self) -> Option<&str> {
+        self.content.as_deref().or(self.summary.as_deref())
+    }
+
+    /// Whether the title, text, authors or categories contain `query`.
+    ///
+    /// `query` must already be lowercase.
+    #[must_use]
+    pub fn matches(&self, query: &str) -> bool {
+        let contains = |s: &str| s.to_lowercase().contains(query);
+
+        contains(&self.title)
+            || self.summary.as_deref().is_some_and(contains)
+            || self.content.as_deref().is_some_and(contains)
+            || self.authors.iter().any(|a| contains(a))
+            || self.categories.iter().any(|c| contains(c))
+    }
+
+    /// Set the item as read, which also acknowledges any change and drops
+    /// the text from before it.
+    pub fn mark_read(&mut self) {
         self.read = true;
+        self.changed = false;
+        self.previous_body = None;
     }
 
     /// Set the item as unread.
@@ -79,7 +228,262 @@ impl FeedItem {
     }
 
     /// Toggle read state.
-    pub const fn toggle_read(&mut self) {
-        self.read = !self.read;
+    pub fn toggle_read(&mut self) {
+        if self.read {
+            self.mark_unread();
+        } else {
+            self.mark_read();
+        }
+    }
+}
+
+/// Whether an item ID predates stable hashing, see [`ID_LEN`].
+pub(super) const fn is_legacy_id(id: &str) -> bool {
+    id.len() != ID_LEN
+}
+
+/// SHA-256 of `bytes`, as hex, truncated to [`ID_LEN`] digits.
+fn short_hash(bytes: &[u8]) -> String {
+    let mut hash = String::with_capacity(ID_LEN);
+    for byte in &Sha256::digest(bytes)[..ID_LEN / 2] {
+        let _ = write!(hash, "{byte:02x}");
+    }
+    hash
+}
+
+/// What was known about an item before a refresh.
+#[derive(Debug, Clone, Default)]
+pub struct KnownItem {
+    /// Whether it had been read.
+    pub read: bool,
+
+    /// Its content hash; empty for items cached before hashes were kept.
+    pub content_hash: String,
+
+    /// Its text.
+    pub body: Option<String>,
+
+    /// Whether it was already flagged as changed.
+    pub changed: bool,
+
+    /// Its text before an earlier change.
+    pub previous_body: Option<String>,
+
+    /// Whether it was starred.
+    pub starred: bool,
+
+    /// When it was first seen, if known.
+    pub first_seen: Option<DateTime<Utc>>,
+}
+
+impl KnownItem {
+    /// Remember the state of an item.
+    #[must_use]
+    pub fn of(item: &FeedItem) -> Self {
+        Self {
+            read: item.read,
+            content_hash: item.content_hash.clone(),
+            body: item.body().map(ToString::to_string),
+            changed: item.changed,
+            previous_body: item.previous_body.clone(),
+            starred: item.starred,
+            first_seen: None,
+        }
+    }
+
+    /// Whether `content_hash` belongs to a different version of the item.
+    ///
+    /// Items without a hash on either side never count as edited, so
+    /// upgrading doesn't flag the whole cache.
+    #[must_use]
+    pub fn is_edited(&self, content_hash: &str) -> bool {
+        !self.content_hash.is_empty()
+            && !content_hash.is_empty()
+            && self.content_hash != content_hash
+    }
+
+    /// Carry the state over to a fresh copy of the item.
+    ///
+    /// If the text changed after the item was read, it is flagged as changed
+    /// and keeps the version that was read to compare against. With
+    /// `mark_unread` it also becomes unread again.
+    pub fn apply_to(&self, item: &mut FeedItem, mark_unread: bool) {
+        item.starred = self.starred;
+        if !self.is_edited(&item.content_hash) {
+            item.read = self.read;
+            item.changed = self.changed;
+            item.previous_body = self.previous_body.clone().filter(|_| self.changed);
+            return;
+        }
+
+        if self.read || self.changed {
+            // Compare against the version that was read, not an unseen edit
+            item.previous_body = if self.changed {
+                self.previous_body.clone()
+            } else {
+                self.body.clone()
+            };
+            item.changed = true;
+            item.read = self.read && !mark_unread;
+        } else {
+            item.read = false;
+        }
+    }
+}
+
+/// Read states of known items, to carry over to freshly fetched ones.
+///
+/// Items are matched by ID. Items cached before IDs were stable are also
+/// matched by link, or by title if they have no link, so upgrading doesn't
+/// make everything unread again.
+#[derive(Debug, Default)]
+pub struct ReadStates {
+    by_id: HashMap<String, KnownItem>,
+    legacy: HashMap<String, KnownItem>,
+}
+
+impl ReadStates {
+    /// Remember the read state of an item.
+    pub fn insert(&mut self, id: &str, link: Option<&str>, title: &str, read: bool) {
+        self.insert_known(
+            id,
+            link,
+            title,
+            KnownItem {
+                read,
+                ..KnownItem::default()
+            },
+        );
+    }
+
+    /// Remember everything known about an item.
+    pub fn insert_known(&mut self, id: &str, link: Option<&str>, title: &str, known: KnownItem) {
+        if is_legacy_id(id) {
+            self.legacy
+                .entry(link.unwrap_or(title).to_string())
+                .or_insert_with(|| known.clone());
+        }
+        self.by_id.insert(id.to_string(), known);
+    }
+
+    /// Look up the read state of an item.
+    #[must_use]
+    pub fn get(&self, id: &str, link: Option<&str>, title: &str) -> Option<bool> {
+        self.get_known(id, link, title).map(|k| k.read)
+    }
+
+    /// Look up everything known about an item.
+    #[must_use]
+    pub fn get_known(&self, id: &str, link: Option<&str>, title: &str) -> Option<&KnownItem> {
+        self.by_id
+            .get(id)
+            .or_else(|| self.legacy.get(link.unwrap_or(title)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_id() {
+        let id = FeedItem::generate_id(Some("tag:example.com,2024:1"), Some("https://a"), "T");
+        assert_eq!(id.len(), ID_LEN);
+
+        // The guid wins, so a changed link keeps the ID
+        assert_eq!(
+            id,
+            FeedItem::generate_id(Some("tag:example.com,2024:1"), Some("https://b"), "T")
+        );
+        assert_ne!(id, FeedItem::generate_id(None, Some("https://a"), "T"));
+
+        // Pinned so a change to the hashing is caught
+        assert_eq!(
+            FeedItem::generate_id(None, None, "Title"),
+            "7e8cd2056da73a7fefb6cd91f4e5d199"
+        );
+    }
+
+    #[test]
+    fn test_matches() {
+        let item = FeedItem {
+            authors: vec!["Ada Lovelace".to_string()],
+            categories: vec!["Rust".to_string()],
+            content: Some("All about the borrow checker".to_string()),
+            ..FeedItem::new("Weekly news".to_string())
+        };
+
+        assert!(item.matches("weekly"));
+        assert!(item.matches("lovelace"));
+        assert!(item.matches("rust"));
+        assert!(item.matches("borrow"));
+        assert!(!item.matches("python"));
+    }
+
+    #[test]
+    fn test_edit_after_reading_is_flagged() {
+        let body = |text: &str| FeedItem {
+            content: Some(text.to_string()),
+            content_hash: FeedItem::hash_content("Post", Some(text)),
+            ..FeedItem::new("Post".to_string())
+        };
+
+        let mut read = body("first draft");
+        read.read = true;
+        let known = KnownItem::of(&read);
+
+        // Unchanged: state carries over
+        let mut same = body("first draft");
+        known.apply_to(&mut same, true);
+        assert!(same.read && !same.changed);
+
+        // Edited: flagged, the read version kept, unread again if asked
+        let mut edited = body("second draft");
+        known.apply_to(&mut edited, false);
+        assert!(edited.read && edited.changed);
+        assert_eq!(edited.previous_body.as_deref(), Some("first draft"));
+
+        let mut edited = body("second draft");
+        known.apply_to(&mut edited, true);
+        assert!(!edited.read && edited.changed);
+
+        // A second edit before it was seen still compares to the read version
+        let mut again = body("third draft");
+        KnownItem::of(&edited).apply_to(&mut again, true);
+        assert_eq!(again.previous_body.as_deref(), Some("first draft"));
+
+        // Reading it acknowledges the change, and the old text goes
+        again.mark_read();
+        let mut refetched = body("third draft");
+        KnownItem::of(&again).apply_to(&mut refetched, true);
+        assert!(refetched.read && !refetched.changed && refetched.previous_body.is_none());
+
+        // Edits to unread items aren't worth flagging
+        let mut unread = body("first draft");
+        unread.read = false;
+        let mut edited = body("second draft");
+        KnownItem::of(&unread).apply_to(&mut edited, true);
+        assert!(!edited.changed && edited.previous_body.is_none());
+    }
+
+    #[test]
+    fn test_read_states_migrate_legacy_ids() {
+        let mut states = ReadStates::default();
+        states.insert("1a2b3c", Some("https://example.com/1"), "One", true);
+        let current = FeedItem::generate_id(None, Some("https://example.com/2"), "Two");
+        states.insert(&current, Some("https://example.com/2"), "Two", true);
+
+        let new_id = FeedItem::generate_id(Some("guid-1"), Some("https://example.com/1"), "One");
+        assert_eq!(
+            states.get(&new_id, Some("https://example.com/1"), "One"),
+            Some(true)
+        );
+
+        // Current IDs don't fall back to the link
+        let other = FeedItem::generate_id(Some("guid-3"), Some("https://example.com/2"), "Three");
+        assert_eq!(
+            states.get(&other, Some("https://example.com/2"), "Three"),
+            None
+        );
     }
 }
@@ -7,11 +7,18 @@ use color_eyre::Result;
 use tracing::{debug, info, warn};
 
 use super::{
-    FeedItem,
+    FeedItem, FeedMeta, KnownItem, ReadStates, Retention,
     cache::{CachedItem, FeedCache},
-    parser,
+    health::{self, FeedHealth, HealthStatus},
+    refresh::{FetchOutcome, FetchRequest, FetchResponse, RefreshJob, RefreshLimits},
+    schedule,
 };
-use crate::config::Config;
+use crate::config::{Config, FeedConfig};
+use crate::http::{self, ClientSettings, RequestOptions};
+
+/// Number of fetches in a row that must be permanently redirected to the
+/// same URL before the feed is moved there.
+const MOVE_AFTER_REDIRECTS: u32 = 3;
 
 /// A single feed with its items.
 #[derive(Debug, Clone)]
@@ -22,26 +29,54 @@ pub struct Feed {
     /// Feed URL.
     pub url: String,
 
-    /// Fetched items.
+    /// Items in the feed, plus unread ones that have dropped out of it.
     pub items: Vec<FeedItem>,
 
+    /// Read items that have dropped out of the feed, newest first.
+    pub archive: Vec<FeedItem>,
+
+    /// Channel metadata published by the feed.
+    pub meta: FeedMeta,
+
     /// Last successful update time.
     pub last_updated: Option<DateTime<Utc>>,
 
     /// Last error message (if any).
     pub error: Option<String>,
+
+    /// Last time a fetch was attempted, successful or not.
+    pub last_checked: Option<DateTime<Utc>>,
+
+    /// Fixed refresh interval in minutes, overriding the adaptive schedule.
+    pub refresh_interval: Option<u32>,
+
+    /// Retention policy for its archived items.
+    pub retention: Retention,
+
+    /// HTTP settings for requests to this feed.
+    pub http: RequestOptions,
+
+    /// Client for feeds routed through their own proxy, or why it couldn't be built.
+    pub client: Option<std::result::Result<reqwest::Client, String>>,
 }
 
 impl Feed {
     /// Create a new feed.
     #[must_use]
-    pub const fn new(name: String, url: String) -> Self {
+    pub fn new(name: String, url: String) -> Self {
         Self {
             name,
             url,
             items: Vec::new(),
+            archive: Vec::new(),
+            meta: FeedMeta::default(),
             last_updated: None,
             error: None,
+            last_checked: None,
+            refresh_interval: None,
+            retention: Retention::default(),
+            http: RequestOptions::default(),
+            client: None,
         }
     }
 
@@ -51,10 +86,21 @@ impl Feed {
         self.items.iter().filter(|i| !i.read).count()
     }
 
+    /// The feed's website, falling back to the origin of the feed URL.
+    #[must_use]
+    pub fn site_url(&self) -> Option<String> {
+        self.meta.site_url.clone().or_else(|| {
+            reqwest::Url::parse(&self.url)
+                .ok()
+                .filter(|u| u.has_host())
+                .map(|u| u.origin().ascii_serialization())
+        })
+    }
+
     /// Mark all items as read.
     pub fn mark_all_read(&mut self) {
         for item in &mut self.items {
-            item.read = true;
+            item.mark_read();
         }
     }
 }
@@ -88,6 +134,19 @@ impl Folder {
     }
 }
 
+/// A feed that was moved to a new URL after repeated permanent redirects.
+#[derive(Debug, Clone)]
+pub struct FeedMoved {
+    /// Feed name.
+    pub name: String,
+
+    /// Old URL.
+    pub from: String,
+
+    /// New URL.
+    pub to: String,
+}
+
 /// Manages all feeds and folders.
 pub struct FeedManager {
     /// All feeds (flat list).
@@ -96,8 +155,20 @@ pub struct FeedManager {
     /// Folder structure.
     pub folders: Vec<Folder>,
 
-    /// HTTP client for fetching.
-    client: reqwest::Client,
+    /// HTTP client for fetching, or why it couldn't be built.
+    client: std::result::Result<reqwest::Client, String>,
+
+    /// Concurrency limits for refreshes.
+    limits: RefreshLimits,
+
+    /// Global refresh interval in minutes (0 = manual only).
+    default_interval: u32,
+
+    /// Days a feed may keep failing before it is suspended (0 = never).
+    dead_feed_days: u32,
+
+    /// Whether items edited after they were read become unread again.
+    mark_updated_unread: bool,
 
     /// Offline cache.
     pub cache: FeedCache,
@@ -106,21 +177,51 @@ pub struct FeedManager {
 impl FeedManager {
     /// Create a new feed manager from configuration.
     ///
-    /// # Errors
+    /// If the HTTP client can't be built, e.g. because the proxy is invalid,
+    /// refreshes fail with the reason.
+    #[must_use]
+    pub fn new(config: &Config) -> Self {
+        // Load cache; a read-only instance leaves the files alone
+        let cache = if config.read_only {
+            FeedCache::load_read_only()
+        } else {
+            FeedCache::load()
+        };
+        let mut cache = cache.unwrap_or_else(|e| {
+            warn!("Starting with an empty cache: {e}");
+            FeedCache::default()
+        });
+        if config.read_only {
+            cache.set_read_only();
+        }
+
+        Self::with_cache(config, cache)
+    }
+
+    /// Rebuild the feeds and folders from `config`, e.g. after subscriptions
+    /// changed.
     ///
-    /// Returns an error if the HTTP client cannot be created.
-    pub fn new(config: &Config) -> Result<Self> {
-        let client = reqwest::Client::builder()
-            .user_agent(concat!("feedo/", env!("CARGO_PKG_VERSION")))
-            .timeout(std::time::Duration::from_secs(30))
-            .build()?;
+    /// The cache carries over as it is in memory, unsaved changes included,
+    /// rather than being loaded from disk again.
+    pub fn reload(&mut self, config: &Config) {
+        self.copy_flags_to_cache();
+        let cache = std::mem::take(&mut self.cache);
+        *self = Self::with_cache(config, cache);
+    }
 
-        // Load cache
-        let cache = FeedCache::load().unwrap_or_default();
+    /// Set up the feeds and folders in `config` from `cache`.
+    fn with_cache(config: &Config, cache: FeedCache) -> Self {
+        let client = fetch_client(&ClientSettings::global(config)).map_err(|e| {
+            warn!("Feeds can't be fetched: {e}");
+            e.to_string()
+        });
 
         let mut feeds: Vec<Feed> = Vec::new();
         let mut folders: Vec<Folder> = Vec::new();
 
+        // Feeds sharing a proxy share a client
+        let mut proxied = HashMap::new();
+
         // Process folders
         for folder_config in &config.folders {
             let mut folder = Folder::new(
@@ -130,18 +231,15 @@ impl FeedManager {
             );
 
             for feed_config in &folder_config.feeds {
-                let feed_idx = feeds.len();
-                let mut feed = Feed::new(feed_config.name.clone(), feed_config.url.clone());
-
-                // Load cached data if available
-                if let Some(cached) = cache.get(&feed_config.url) {
-                    feed.items = cached_to_items(&cached.items);
-                    feed.last_updated = cached.last_fetched;
-                    info!("Loaded {} cached items for {}", feed.items.len(), feed.name);
-                }
-
-                feeds.push(feed);
-                folder.feed_indices.push(feed_idx);
+                folder.feed_indices.push(feeds.len());
+                let retention = folder_config.retention.or(config.retention);
+                feeds.push(load_feed(
+                    feed_config,
+                    retention,
+                    config,
+                    &cache,
+                    &mut proxied,
+                ));
             }
 
             folders.push(folder);
@@ -149,30 +247,147 @@ impl FeedManager {
 
         // Process root-level feeds
         for feed_config in &config.feeds {
-            let mut feed = Feed::new(feed_config.name.clone(), feed_config.url.clone());
-
-            // Load cached data if available
-            if let Some(cached) = cache.get(&feed_config.url) {
-                feed.items = cached_to_items(&cached.items);
-                feed.last_updated = cached.last_fetched;
-                info!("Loaded {} cached items for {}", feed.items.len(), feed.name);
-            }
-
-            feeds.push(feed);
+            feeds.push(load_feed(
+                feed_config,
+                config.retention,
+                config,
+                &cache,
+                &mut proxied,
+            ));
         }
 
-        Ok(Self {
+        let limits = RefreshLimits {
+            max_concurrent: config.max_concurrent_fetches,
+            max_per_host: config.max_fetches_per_host,
+        };
+
+        Self {
             feeds,
             folders,
             client,
+            limits,
+            default_interval: config.refresh_interval,
+            dead_feed_days: config.dead_feed_days,
+            mark_updated_unread: config.mark_updated_unread,
             cache,
-        })
+        }
+    }
+
+    /// Start refreshing the feeds at the given indices in the background.
+    ///
+    /// Outcomes must be passed to [`Self::apply_outcome`] as they arrive.
+    #[must_use]
+    pub fn start_refresh(&self, indices: impl IntoIterator<Item = usize>) -> RefreshJob {
+        let requests: Vec<FetchRequest> = indices
+            .into_iter()
+            .filter_map(|i| self.feeds.get(i))
+            .map(|f| self.fetch_request(f))
+            .collect();
+
+        debug!("Refreshing {} feeds", requests.len());
+        RefreshJob::spawn(&self.client, requests, self.limits)
+    }
+
+    /// Build a fetch request, attaching cache validators when we have items to fall back on.
+    fn fetch_request(&self, feed: &Feed) -> FetchRequest {
+        let cached = self.cache.get(&feed.url).filter(|c| !c.items.is_empty());
+        FetchRequest {
+            url: feed.url.clone(),
+            etag: cached.and_then(|c| c.etag.clone()),
+            last_modified: cached.and_then(|c| c.last_modified.clone()),
+            options: feed.http.clone(),
+            client: feed.client.clone(),
+        }
+    }
+
+    /// Start refreshing all feeds in the background.
+    #[must_use]
+    pub fn start_refresh_all(&self) -> RefreshJob {
+        self.start_refresh(0..self.feeds.len())
+    }
+
+    /// When the feed at `index` is next due for an automatic refresh.
+    ///
+    /// Failing feeds are retried with exponential backoff. Returns `None` if
+    /// automatic refreshing is disabled for the feed, either globally or by
+    /// its own override, or if the feed is dead.
+    #[must_use]
+    pub fn next_due(&self, index: usize, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if self.default_interval == 0 {
+            return None;
+        }
+
+        let feed = self.feeds.get(index)?;
+        let cached = self.cache.get(&feed.url);
+        let hints = cached.map(|c| c.schedule.clone()).unwrap_or_default();
+
+        if let Some(cached) = cached.filter(|c| c.failures > 0) {
+            if feed.refresh_interval == Some(0)
+                || FeedHealth::of(cached, self.dead_feed_days, now).status == HealthStatus::Dead
+            {
+                return None;
+            }
+            let delay = health::retry_delay(cached.failures);
+            return Some(schedule::next_due(feed.last_checked, delay, &hints, now));
+        }
+        let published: Vec<DateTime<Utc>> = feed.items.iter().filter_map(|i| i.published).collect();
+
+        let interval = schedule::refresh_interval(
+            &hints,
+            &published,
+            feed.refresh_interval,
+            self.default_interval,
+            now,
+        )?;
+
+        Some(schedule::next_due(feed.last_checked, interval, &hints, now))
+    }
+
+    /// Indices of feeds that are due for an automatic refresh.
+    #[must_use]
+    pub fn due_feeds(&self, now: DateTime<Utc>) -> Vec<usize> {
+        (0..self.feeds.len())
+            .filter(|&i| self.next_due(i, now).is_some_and(|due| due <= now))
+            .collect()
+    }
+
+    /// The earliest time any feed is due for an automatic refresh.
+    #[must_use]
+    pub fn next_refresh_at(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        (0..self.feeds.len())
+            .filter_map(|i| self.next_due(i, now))
+            .min()
+    }
+
+    /// Health of the feed at `index`.
+    #[must_use]
+    pub fn health(&self, index: usize, now: DateTime<Utc>) -> Option<FeedHealth> {
+        let feed = self.feeds.get(index)?;
+        self.cache
+            .get(&feed.url)
+            .map(|c| FeedHealth::of(c, self.dead_feed_days, now))
+    }
+
+    /// Feeds whose last fetch failed, dead ones first, then by failure count.
+    #[must_use]
+    pub fn broken_feeds(&self, now: DateTime<Utc>) -> Vec<(usize, FeedHealth)> {
+        let mut broken: Vec<(usize, FeedHealth)> = (0..self.feeds.len())
+            .filter_map(|i| self.health(i, now).map(|h| (i, h)))
+            .filter(|(_, h)| h.status != HealthStatus::Healthy)
+            .collect();
+        broken.sort_by(|(_, a), (_, b)| {
+            a.status
+                .cmp(&b.status)
+                .then_with(|| b.failures.cmp(&a.failures))
+        });
+        broken
     }
 
     /// Refresh all feeds.
     pub async fn refresh_all(&mut self) {
-        for i in 0..self.feeds.len() {
-            self.refresh_feed(i).await;
+        let mut job = self.start_refresh_all();
+        while let Some(outcome) = job.next().await {
+            let _ = self.apply_outcome(outcome);
         }
 
         // Save cache after refresh
@@ -183,25 +398,78 @@ impl FeedManager {
 
     /// Refresh a single feed by index.
     pub async fn refresh_feed(&mut self, index: usize) {
-        let Some(feed) = self.feeds.get(index) else {
-            return;
-        };
+        let mut job = self.start_refresh([index]);
+        while let Some(outcome) = job.next().await {
+            let _ = self.apply_outcome(outcome);
+        }
+    }
 
-        let url = feed.url.clone();
+    /// Merge the outcome of a fetch into the feed list and cache.
+    ///
+    /// Returns the move if the feed has been permanently redirected often
+    /// enough to switch to its new URL. The caller should update the config.
+    pub fn apply_outcome(&mut self, outcome: FetchOutcome) -> Option<FeedMoved> {
+        let FetchOutcome {
+            url,
+            status,
+            moved_to,
+            result,
+        } = outcome;
+
+        // Feeds may have been removed while the fetch was in flight
+        let Some(index) = self.feeds.iter().position(|f| f.url == url) else {
+            debug!("Dropping fetch result for removed feed: {url}");
+            return None;
+        };
+        let succeeded = result.is_ok();
+        let feed = &mut self.feeds[index];
+        feed.last_checked = Some(Utc::now());
         let name = feed.name.clone();
 
-        debug!("Fetching feed: {name} ({url})");
+        // Get current read states to preserve, and make sure the cache has
+        // them too for the items that are about to be archived
+        let mut read_states = ReadStates::default();
+        for item in feed.items.iter().chain(&feed.archive) {
+            read_states.insert_known(
+                &item.id,
+                item.link.as_deref(),
+                &item.title,
+                KnownItem::of(item),
+            );
+        }
+        for item in &feed.items {
+            self.cache.set_item_read(&url, &item.id, item.read);
+            self.cache.set_item_changed(&url, &item.id, item.changed);
+            self.cache.set_item_starred(&url, &item.id, item.starred);
+        }
+        let retention = feed.retention;
 
-        // Get current read states to preserve
-        let read_states: HashMap<String, bool> =
-            feed.items.iter().map(|i| (i.id.clone(), i.read)).collect();
+        match result {
+            Ok(FetchResponse::NotModified) => {
+                debug!("{name} not modified");
+                self.cache.mark_not_modified(&url);
 
-        match self.fetch_feed(&url).await {
-            Ok(mut items) => {
-                // Restore read states from memory
+                if let Some(feed) = self.feeds.get_mut(index) {
+                    feed.last_updated = Some(Utc::now());
+                    feed.error = None;
+                }
+            }
+            Ok(FetchResponse::Updated {
+                mut items,
+                schedule,
+                meta,
+                etag,
+                last_modified,
+            }) => {
+                // Restore read states from memory, flagging edited items.
+                // Items pruned after reading stay read if they come back
                 for item in &mut items {
-                    if let Some(&was_read) = read_states.get(&item.id) {
-                        item.read = was_read;
+                    if let Some(known) =
+                        read_states.get_known(&item.id, item.link.as_deref(), &item.title)
+                    {
+                        known.apply_to(item, self.mark_updated_unread);
+                    } else if self.cache.was_read(&url, &item.id) {
+                        item.read = true;
                     }
                 }
 
@@ -212,17 +480,39 @@ impl FeedManager {
                         id: i.id.clone(),
                         title: i.title.clone(),
                         link: i.link.clone(),
+                        alternate_links: i.alternate_links.clone(),
                         published: i.published,
+                        updated: i.updated,
+                        authors: i.authors.clone(),
+                        categories: i.categories.clone(),
                         summary: i.summary.clone(),
+                        content: i.content.clone(),
+                        enclosures: i.enclosures.clone(),
+                        content_hash: i.content_hash.clone(),
+                        changed: i.changed,
+                        previous_body: i.previous_body.clone(),
+                        archived: false,
+                        starred: i.starred,
                         read: i.read,
                         cached_at: Utc::now(),
                     })
                     .collect();
 
                 self.cache.update_feed(&url, &name, cached_items, None);
-
+                self.cache.set_validators(&url, etag, last_modified);
+                self.cache.set_schedule(&url, schedule);
+                self.cache.set_meta(&url, (*meta).clone());
+                self.cache.prune(&url, &retention);
+
+                // The cache now holds the fresh items followed by the archive
+                let merged = self
+                    .cache
+                    .get(&url)
+                    .map(|c| cached_to_items(&c.items))
+                    .unwrap_or(items);
                 if let Some(feed) = self.feeds.get_mut(index) {
-                    feed.items = items;
+                    (feed.items, feed.archive) = split_archive(merged);
+                    feed.meta = *meta;
                     feed.last_updated = Some(Utc::now());
                     feed.error = None;
                     debug!("Fetched {} items from {name}", feed.items.len());
@@ -241,13 +531,34 @@ impl FeedManager {
                 }
             }
         }
+
+        self.cache.set_http_status(&url, status);
+
+        // Only trust redirects that led to a working feed
+        let moved_to = moved_to.filter(|_| succeeded);
+        if self.cache.record_redirect(&url, moved_to.as_deref()) >= MOVE_AFTER_REDIRECTS {
+            return moved_to.and_then(|to| self.move_feed(index, to));
+        }
+        None
     }
 
-    /// Fetch and parse a feed from URL.
-    async fn fetch_feed(&self, url: &str) -> Result<Vec<FeedItem>> {
-        let response = self.client.get(url).send().await?;
-        let bytes = response.bytes().await?;
-        parser::parse_feed(&bytes)
+    /// Switch a feed to a new URL, carrying its cached data over.
+    fn move_feed(&mut self, index: usize, to: String) -> Option<FeedMoved> {
+        if self.feeds.iter().any(|f| f.url == to) {
+            warn!("Not moving feed to {to}: already subscribed");
+            return None;
+        }
+
+        let feed = self.feeds.get_mut(index)?;
+        let from = std::mem::replace(&mut feed.url, to.clone());
+        self.cache.rename_feed(&from, &to);
+        info!("{} moved permanently: {from} -> {to}", feed.name);
+
+        Some(FeedMoved {
+            name: feed.name.clone(),
+            from,
+            to,
+        })
     }
 
     /// Toggle folder expansion.
@@ -290,33 +601,49 @@ impl FeedManager {
             .collect()
     }
 
-    /// Save the current state to cache.
-    pub fn save_cache(&mut self) {
-        // Update all feeds in cache with current read states
-        for feed in &self.feeds {
-            let cached_items: Vec<CachedItem> = feed
+    /// Copy read states from the cache onto the in-memory items.
+    pub fn reload_read_states(&mut self) {
+        for feed in &mut self.feeds {
+            let Some(cached) = self.cache.get(&feed.url) else {
+                continue;
+            };
+            let states: HashMap<&str, bool> = cached
                 .items
                 .iter()
-                .map(|i| CachedItem {
-                    id: i.id.clone(),
-                    title: i.title.clone(),
-                    link: i.link.clone(),
-                    published: i.published,
-                    summary: i.summary.clone(),
-                    read: i.read,
-                    cached_at: Utc::now(),
-                })
+                .map(|i| (i.id.as_str(), i.read))
                 .collect();
-
-            self.cache
-                .update_feed(&feed.url, &feed.name, cached_items, feed.error.clone());
+            for item in &mut feed.items {
+                if let Some(&read) = states.get(item.id.as_str()) {
+                    item.read = read;
+                }
+            }
         }
+    }
 
+    /// Save the current state to cache.
+    ///
+    /// Items and fetch times are already cached as fetches complete, so only
+    /// read states need copying over.
+    pub fn save_cache(&mut self) {
+        self.copy_flags_to_cache();
         if let Err(e) = self.cache.save() {
             warn!("Failed to save cache: {e}");
         }
     }
 
+    /// Copy the items' read, changed and starred flags to the cache.
+    fn copy_flags_to_cache(&mut self) {
+        for feed in &self.feeds {
+            for item in &feed.items {
+                self.cache.set_item_read(&feed.url, &item.id, item.read);
+                self.cache
+                    .set_item_changed(&feed.url, &item.id, item.changed);
+                self.cache
+                    .set_item_starred(&feed.url, &item.id, item.starred);
+            }
+        }
+    }
+
     /// Get cache statistics.
     #[must_use]
     pub fn cache_stats(&self) -> super::CacheStats {
@@ -324,6 +651,66 @@ impl FeedManager {
     }
 }
 
+/// Build the client used to fetch feeds.
+fn fetch_client(settings: &ClientSettings) -> Result<reqwest::Client> {
+    // Redirects are followed by the refresh code, which tracks permanent moves
+    Ok(http::client_builder(settings)?
+        .timeout(std::time::Duration::from_secs(30))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?)
+}
+
+/// Create a feed from its configuration, with any cached items.
+///
+/// `retention` is the policy of the feed's folder, or the global one. A feed
+/// whose proxy is unusable is marked as failing and isn't fetched.
+fn load_feed(
+    feed_config: &FeedConfig,
+    retention: Retention,
+    config: &Config,
+    cache: &FeedCache,
+    proxied: &mut HashMap<String, std::result::Result<reqwest::Client, String>>,
+) -> Feed {
+    let mut feed = Feed::new(feed_config.name.clone(), feed_config.url.clone());
+    feed.refresh_interval = feed_config.refresh_interval;
+    feed.retention = feed_config.retention.or(retention);
+    feed.http = RequestOptions::for_feed(feed_config);
+
+    if let Some(proxy) = &feed_config.proxy {
+        let client = proxied
+            .entry(proxy.clone())
+            .or_insert_with(|| {
+                fetch_client(&ClientSettings::for_feed(feed_config, config))
+                    .map_err(|e| e.to_string())
+            })
+            .clone();
+        if let Err(e) = &client {
+            warn!("Not fetching {}: {e}", feed.name);
+            feed.error = Some(e.clone());
+        }
+        feed.client = Some(client);
+    }
+
+    // Load cached data if available
+    if let Some(cached) = cache.get(&feed_config.url) {
+        (feed.items, feed.archive) = split_archive(cached_to_items(&cached.items));
+        feed.meta = cached.meta.clone();
+        feed.last_updated = cached.last_fetched;
+        feed.last_checked = cached.last_fetched;
+        info!("Loaded {} cached items for {}", feed.items.len(), feed.name);
+    }
+
+    feed
+}
+
+/// Separate the read archived items from the ones to list.
+///
+/// Archived items stay in the list until they are read, so nothing unread
+/// disappears just because it fell off the feed.
+fn split_archive(items: Vec<FeedItem>) -> (Vec<FeedItem>, Vec<FeedItem>) {
+    items.into_iter().partition(|i| !i.archived || !i.read)
+}
+
 /// Convert cached items to feed items.
 fn cached_to_items(cached: &[CachedItem]) -> Vec<FeedItem> {
     cached
@@ -332,8 +719,19 @@ fn cached_to_items(cached: &[CachedItem]) -> Vec<FeedItem> {
             id: c.id.clone(),
             title: c.title.clone(),
             link: c.link.clone(),
+            alternate_links: c.alternate_links.clone(),
             published: c.published,
+            updated: c.updated,
+            authors: c.authors.clone(),
+            categories: c.categories.clone(),
             summary: c.summary.clone(),
+            content: c.content.clone(),
+            enclosures: c.enclosures.clone(),
+            content_hash: c.content_hash.clone(),
+            changed: c.changed,
+            previous_body: c.previous_body.clone(),
+            archived: c.archived,
+            starred: c.starred,
             read: c.read,
         })
         .collect()
@@ -0,0 +1,92 @@
+//! Channel-level feed metadata.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// What a feed says about itself: description, website, language and so on.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedMeta {
+    /// Title published by the feed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// Description or subtitle.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// The website the feed belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site_url: Option<String>,
+
+    /// Language code, e.g. `en-us`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
+    /// Software that produced the feed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator: Option<String>,
+
+    /// Icon or logo URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+
+    /// Feed-level author names.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<String>,
+
+    /// When the publisher last rebuilt the feed (RSS `lastBuildDate`, Atom `updated`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_build_date: Option<DateTime<Utc>>,
+}
+
+impl FeedMeta {
+    /// Extract the metadata from a parsed feed.
+    #[must_use]
+    pub fn from_feed(feed: &feed_rs::model::Feed) -> Self {
+        let non_empty = |s: &str| {
+            let s = s.trim();
+            (!s.is_empty()).then(|| s.to_string())
+        };
+
+        // RSS `<link>` has no rel; Atom marks the website as "alternate"
+        let site_url = feed
+            .links
+            .iter()
+            .find(|l| l.rel.as_deref().is_none_or(|rel| rel == "alternate"))
+            .map(|l| l.href.clone());
+
+        Self {
+            title: feed.title.as_ref().and_then(|t| non_empty(&t.content)),
+            description: feed
+                .description
+                .as_ref()
+                .and_then(|t| non_empty(&t.content)),
+            site_url,
+            language: feed.language.as_deref().and_then(non_empty),
+            generator: feed.generator.as_ref().and_then(|g| {
+                let name = non_empty(&g.content)?;
+                Some(match &g.version {
+                    Some(version) => format!("{name} {version}"),
+                    None => name,
+                })
+            }),
+            icon: feed
+                .icon
+                .as_ref()
+                .or(feed.logo.as_ref())
+                .map(|i| i.uri.clone()),
+            authors: feed
+                .authors
+                .iter()
+                .filter_map(|p| non_empty(&p.name))
+                .collect(),
+            last_build_date: feed.updated,
+        }
+    }
+
+    /// Whether nothing is known about the feed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
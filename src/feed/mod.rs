@@ -1,19 +1,38 @@
 //! Feed management and fetching.
 //!
 //! This module handles:
-//! - Fetching RSS/Atom feeds from the network
-//! - Parsing feed content
+//! - Fetching RSS/Atom feeds from the network, concurrently
+//! - Parsing feed content and channel metadata
 //! - Managing feed state (read/unread)
+//! - Noticing and diffing edited articles
+//! - Archiving articles that drop out of a feed, within a retention policy
+//! - Tracking feed health and backing off failing feeds
+//! - Scheduling per-feed refreshes
 //! - Auto-discovering feeds from URLs
 //! - Offline caching of feed data
 
 mod cache;
+mod diff;
 mod discovery;
+mod health;
 mod item;
 mod manager;
+mod meta;
 mod parser;
+mod refresh;
+mod retention;
+mod schedule;
+mod store;
 
 pub use cache::{CacheStats, CachedFeed, CachedItem, FeedCache};
+pub use diff::{DiffSpan, word_diff};
 pub use discovery::{DiscoveredFeed, FeedDiscovery, FeedType};
-pub use item::FeedItem;
-pub use manager::{Feed, FeedManager, Folder};
+pub use health::{FeedHealth, HealthStatus};
+pub use item::{Enclosure, FeedItem, KnownItem, ReadStates, sanitize_file_name};
+pub use manager::{Feed, FeedManager, FeedMoved, Folder};
+pub use meta::FeedMeta;
+pub use refresh::{
+    FetchOutcome, FetchRequest, FetchResponse, RefreshJob, RefreshLimits, RefreshProgress,
+};
+pub use retention::Retention;
+pub use schedule::ScheduleHints;
@@ -1,45 +1,264 @@
 //! Feed parsing utilities.
 
 use color_eyre::Result;
-use feed_rs::parser;
+use feed_rs::{model::Entry, parser};
 
-use super::FeedItem;
+use super::{Enclosure, FeedItem, FeedMeta, schedule::ScheduleHints};
 
-/// Parse raw feed bytes into a list of feed items.
+/// The parts of a parsed feed that we keep.
+#[derive(Debug, Default)]
+pub struct ParsedFeed {
+    /// Feed items.
+    pub items: Vec<FeedItem>,
+
+    /// Refresh hints published by the feed.
+    pub schedule: ScheduleHints,
+
+    /// Channel-level metadata.
+    pub meta: FeedMeta,
+}
+
+/// Parse raw feed bytes into feed items, refresh hints and metadata.
 ///
 /// # Errors
 ///
 /// Returns an error if the feed cannot be parsed.
-pub fn parse_feed(bytes: &[u8]) -> Result<Vec<FeedItem>> {
-    let feed = parser::parse(bytes)?;
+pub fn parse_feed(bytes: &[u8]) -> Result<ParsedFeed> {
+    // feed-rs makes up IDs for entries without one, sometimes random ones;
+    // leave them empty so ours fall back to the link instead
+    let feed = parser::Builder::new()
+        .id_generator(|_, _, _| String::new())
+        .build()
+        .parse(bytes)?;
+    let meta = FeedMeta::from_feed(&feed);
 
     let items = feed
         .entries
         .into_iter()
         .map(|entry| {
+            let enclosures = enclosures(&entry);
             let title = entry
                 .title
                 .map_or_else(|| "Untitled".to_string(), |t| t.content);
 
-            let link = entry.links.first().map(|l| l.href.clone());
-            let published = entry.published.or(entry.updated);
-            let summary = entry
-                .summary
-                .map(|s| s.content)
-                .or_else(|| entry.content.and_then(|c| c.body));
+            // Enclosures are attachments, not pages to open
+            let mut links = entry
+                .links
+                .into_iter()
+                .filter(|l| l.rel.as_deref() != Some("enclosure"))
+                .map(|l| l.href);
+            let link = links.next();
+            let mut alternate_links: Vec<String> = Vec::new();
+            for href in links {
+                if link.as_ref() != Some(&href) && !alternate_links.contains(&href) {
+                    alternate_links.push(href);
+                }
+            }
+
+            let authors = entry
+                .authors
+                .into_iter()
+                .map(|p| p.name)
+                .filter(|n| !n.trim().is_empty())
+                .collect();
+            let categories = entry
+                .categories
+                .into_iter()
+                .map(|c| c.label.unwrap_or(c.term))
+                .filter(|c| !c.trim().is_empty())
+                .collect();
 
-            let id = FeedItem::generate_id(link.as_deref(), &title);
+            let id = FeedItem::generate_id(Some(&entry.id), link.as_deref(), &title);
+            let summary = entry.summary.map(|s| s.content);
+            let content = entry.content.and_then(|c| c.body);
+            let content_hash =
+                FeedItem::hash_content(&title, content.as_deref().or(summary.as_deref()));
 
             FeedItem {
                 id,
                 title,
                 link,
-                published,
+                alternate_links,
+                published: entry.published.or(entry.updated),
+                updated: entry.updated,
+                authors,
+                categories,
                 summary,
+                content,
+                enclosures,
+                content_hash,
+                changed: false,
+                previous_body: None,
+                archived: false,
+                starred: false,
                 read: false,
             }
         })
         .collect();
 
-    Ok(items)
+    Ok(ParsedFeed {
+        items,
+        schedule: ScheduleHints::parse(bytes),
+        meta,
+    })
+}
+
+/// Collect the media attached to an entry, without duplicates.
+///
+/// RSS `<enclosure>` and Media RSS end up in `entry.media`; Atom uses
+/// `rel="enclosure"` links.
+fn enclosures(entry: &Entry) -> Vec<Enclosure> {
+    let media = entry.media.iter().flat_map(|m| {
+        m.content.iter().filter_map(move |c| {
+            Some(Enclosure {
+                url: c.url.as_ref()?.to_string(),
+                mime_type: c.content_type.as_ref().map(ToString::to_string),
+                length: c.size.filter(|&s| s > 0),
+                duration: c.duration.or(m.duration).map(|d| d.as_secs()),
+            })
+        })
+    });
+    let links = entry
+        .links
+        .iter()
+        .filter(|l| l.rel.as_deref() == Some("enclosure"))
+        .map(|l| Enclosure {
+            url: l.href.clone(),
+            mime_type: l.media_type.clone(),
+            length: l.length.filter(|&s| s > 0),
+            duration: None,
+        });
+
+    let mut enclosures: Vec<Enclosure> = Vec::new();
+    for enclosure in media.chain(links) {
+        if !enclosures.iter().any(|e| e.url == enclosure.url) {
+            enclosures.push(enclosure);
+        }
+    }
+    enclosures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item_metadata() {
+        let xml = br#"<?xml version="1.0" encoding="utf-8"?>
+            <feed xmlns="http://www.w3.org/2005/Atom">
+              <title>Example</title>
+              <id>urn:example</id>
+              <updated>2024-01-02T00:00:00Z</updated>
+              <entry>
+                <title>Post</title>
+                <id>urn:example:1</id>
+                <link href="https://example.com/post"/>
+                <link rel="replies" href="https://example.com/post#comments"/>
+                <link rel="enclosure" href="https://example.com/post.mp3"/>
+                <published>2024-01-01T00:00:00Z</published>
+                <updated>2024-01-02T00:00:00Z</updated>
+                <author><name>Ada</name></author>
+                <category term="rust" label="Rust"/>
+                <summary>Short</summary>
+                <content type="html">&lt;p&gt;Long&lt;/p&gt;</content>
+              </entry>
+            </feed>"#;
+
+        let item = &parse_feed(xml).unwrap().items[0];
+        assert_eq!(item.link.as_deref(), Some("https://example.com/post"));
+        assert_eq!(
+            item.alternate_links,
+            vec!["https://example.com/post#comments"]
+        );
+        assert_eq!(item.authors, vec!["Ada"]);
+        assert_eq!(item.categories, vec!["Rust"]);
+        assert_eq!(item.summary.as_deref(), Some("Short"));
+        assert!(item.content.as_deref().is_some_and(|c| c.contains("Long")));
+        assert!(item.updated > item.published);
+        assert_eq!(item.enclosures.len(), 1);
+        assert_eq!(item.enclosures[0].url, "https://example.com/post.mp3");
+    }
+
+    #[test]
+    fn test_rss_enclosure() {
+        let xml = br#"<?xml version="1.0"?>
+            <rss version="2.0">
+              <channel>
+                <title>Podcast</title>
+                <item>
+                  <title>Episode 1</title>
+                  <guid>ep-1</guid>
+                  <enclosure url="https://cdn.example.com/ep%201.mp3" length="1234" type="audio/mpeg"/>
+                </item>
+              </channel>
+            </rss>"#;
+
+        let item = &parse_feed(xml).unwrap().items[0];
+        let enclosure = &item.enclosures[0];
+        assert_eq!(enclosure.url, "https://cdn.example.com/ep%201.mp3");
+        assert_eq!(enclosure.mime_type.as_deref(), Some("audio/mpeg"));
+        assert_eq!(enclosure.length, Some(1234));
+        assert_eq!(enclosure.icon(), "🎧");
+        assert_eq!(enclosure.file_name(), "ep 1.mp3");
+
+        // Files of the same name from different URLs are saved separately
+        let other = Enclosure {
+            url: "https://cdn.example.com/ep-2/ep%201.mp3".to_string(),
+            ..enclosure.clone()
+        };
+        let name = enclosure.download_name();
+        assert!(name.starts_with("ep 1-"));
+        assert_eq!(
+            std::path::Path::new(&name).extension(),
+            Some("mp3".as_ref())
+        );
+        assert_ne!(name, other.download_name());
+    }
+
+    #[test]
+    fn test_feed_meta() {
+        let xml = br#"<?xml version="1.0"?>
+            <rss version="2.0">
+              <channel>
+                <title>Example Blog</title>
+                <link>https://example.com/</link>
+                <description>Notes about things</description>
+                <language>en-us</language>
+                <generator>Hugo</generator>
+                <lastBuildDate>Tue, 02 Jan 2024 10:00:00 GMT</lastBuildDate>
+                <image><url>https://example.com/logo.png</url><title>Logo</title><link>https://example.com/</link></image>
+              </channel>
+            </rss>"#;
+
+        let meta = parse_feed(xml).unwrap().meta;
+        assert_eq!(meta.title.as_deref(), Some("Example Blog"));
+        assert_eq!(meta.site_url.as_deref(), Some("https://example.com/"));
+        assert_eq!(meta.description.as_deref(), Some("Notes about things"));
+        assert_eq!(meta.language.as_deref(), Some("en-us"));
+        assert_eq!(meta.generator.as_deref(), Some("Hugo"));
+        assert_eq!(meta.icon.as_deref(), Some("https://example.com/logo.png"));
+        assert!(meta.last_build_date.is_some());
+    }
+
+    #[test]
+    fn test_item_ids() {
+        let xml = br#"<?xml version="1.0"?>
+            <rss version="2.0">
+              <channel>
+                <title>Example</title>
+                <item><title>One</title><link>https://example.com/1</link><guid>post-1</guid></item>
+                <item><title>Two</title></item>
+              </channel>
+            </rss>"#;
+
+        let first = parse_feed(xml).unwrap().items;
+        assert_eq!(
+            first[0].id,
+            FeedItem::generate_id(Some("post-1"), None, "One")
+        );
+
+        // Entries without a guid or link still get the same ID every time
+        let second = parse_feed(xml).unwrap().items;
+        assert_eq!(first[1].id, second[1].id);
+    }
 }
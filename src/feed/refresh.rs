@@ -0,0 +1,457 @@
+//! Concurrent feed refreshing.
+//!
+//! Feeds are fetched in spawned tasks, bounded by a global concurrency
+//! limit and a per-host cap, and their outcomes are streamed back so the
+//! caller can merge them into the [`FeedManager`](super::FeedManager)
+//! as they arrive.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::Arc,
+};
+
+use color_eyre::{Result, eyre::eyre};
+use reqwest::{StatusCode, header};
+use tokio::sync::{Semaphore, mpsc};
+
+use super::{FeedItem, FeedMeta, parser, schedule::ScheduleHints};
+use crate::http::{self, RequestOptions};
+
+/// Default number of feeds fetched at the same time.
+const DEFAULT_MAX_CONCURRENT: usize = 8;
+
+/// Default number of simultaneous requests to a single host.
+const DEFAULT_MAX_PER_HOST: usize = 2;
+
+/// Maximum number of redirects followed for a single fetch.
+const MAX_REDIRECTS: usize = 10;
+
+/// A feed to fetch, with the cache validators from the previous fetch.
+#[derive(Debug, Clone, Default)]
+pub struct FetchRequest {
+    /// Feed URL.
+    pub url: String,
+
+    /// `ETag` header from the last successful fetch.
+    pub etag: Option<String>,
+
+    /// `Last-Modified` header from the last successful fetch.
+    pub last_modified: Option<String>,
+
+    /// Per-feed HTTP settings.
+    pub options: RequestOptions,
+
+    /// Client to use instead of the shared one, e.g. for a per-feed proxy,
+    /// or why the feed's own client couldn't be built.
+    pub client: Option<std::result::Result<reqwest::Client, String>>,
+}
+
+/// Successful response to a feed fetch.
+#[derive(Debug)]
+pub enum FetchResponse {
+    /// The feed was downloaded and parsed.
+    Updated {
+        /// Parsed items.
+        items: Vec<FeedItem>,
+        /// Refresh hints published by the feed.
+        schedule: ScheduleHints,
+        /// Channel-level metadata.
+        meta: Box<FeedMeta>,
+        /// `ETag` header to send next time.
+        etag: Option<String>,
+        /// `Last-Modified` header to send next time.
+        last_modified: Option<String>,
+    },
+    /// The server answered `304 Not Modified`.
+    NotModified,
+}
+
+/// Result of fetching a single feed.
+#[derive(Debug)]
+pub struct FetchOutcome {
+    /// URL of the feed that was fetched.
+    pub url: String,
+
+    /// HTTP status code, if the server answered at all.
+    pub status: Option<u16>,
+
+    /// Final URL, if every redirect on the way was permanent (301/308).
+    pub moved_to: Option<String>,
+
+    /// The response, or the error that occurred.
+    pub result: Result<FetchResponse>,
+}
+
+/// Progress of a running refresh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RefreshProgress {
+    /// Number of feeds finished (successfully or not).
+    pub done: usize,
+
+    /// Total number of feeds in this refresh.
+    pub total: usize,
+}
+
+impl RefreshProgress {
+    /// Whether every feed has been processed.
+    #[must_use]
+    pub const fn is_finished(&self) -> bool {
+        self.done >= self.total
+    }
+}
+
+impl fmt::Display for RefreshProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.done, self.total)
+    }
+}
+
+/// Concurrency limits for a refresh.
+#[derive(Debug, Clone, Copy)]
+pub struct RefreshLimits {
+    /// Maximum number of feeds fetched at the same time.
+    pub max_concurrent: usize,
+
+    /// Maximum number of simultaneous requests to a single host.
+    pub max_per_host: usize,
+}
+
+impl Default for RefreshLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent: DEFAULT_MAX_CONCURRENT,
+            max_per_host: DEFAULT_MAX_PER_HOST,
+        }
+    }
+}
+
+/// A refresh running in the background.
+///
+/// Outcomes arrive in completion order, not in the order feeds were queued.
+pub struct RefreshJob {
+    rx: mpsc::UnboundedReceiver<FetchOutcome>,
+    progress: RefreshProgress,
+    /// URLs of the feeds still being fetched.
+    pending: HashSet<String>,
+}
+
+impl RefreshJob {
+    /// Spawn fetch tasks for the given feeds.
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub(crate) fn spawn(
+        client: &std::result::Result<reqwest::Client, String>,
+        requests: Vec<FetchRequest>,
+        limits: RefreshLimits,
+    ) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let progress = RefreshProgress {
+            done: 0,
+            total: requests.len(),
+        };
+
+        let global = Arc::new(Semaphore::new(limits.max_concurrent.max(1)));
+        let mut hosts: HashMap<String, Arc<Semaphore>> = HashMap::new();
+        let pending = requests.iter().map(|r| r.url.clone()).collect();
+
+        for request in requests {
+            let host = Arc::clone(
+                hosts
+                    .entry(host_key(&request.url))
+                    .or_insert_with(|| Arc::new(Semaphore::new(limits.max_per_host.max(1)))),
+            );
+            let global = Arc::clone(&global);
+            let client = request.client.clone().unwrap_or_else(|| client.clone());
+            let tx = tx.clone();
+
+            tokio::spawn(async move {
+                // Take the host permit first so a busy host doesn't hog global slots
+                let _host_permit = host.acquire_owned().await;
+                let _permit = global.acquire_owned().await;
+
+                let sent = match &client {
+                    Ok(client) => send_request(client, &request).await,
+                    Err(e) => Err(eyre!("{e}")),
+                };
+                let (status, moved_to, result) = match sent {
+                    Ok((response, moved_to)) => (
+                        Some(response.status().as_u16()),
+                        moved_to,
+                        read_response(response).await,
+                    ),
+                    Err(e) => (None, None, Err(e)),
+                };
+                let _ = tx.send(FetchOutcome {
+                    url: request.url,
+                    status,
+                    moved_to,
+                    result,
+                });
+            });
+        }
+
+        Self {
+            rx,
+            progress,
+            pending,
+        }
+    }
+
+    /// Current progress.
+    #[must_use]
+    pub const fn progress(&self) -> RefreshProgress {
+        self.progress
+    }
+
+    /// Whether the feed at `url` is still waiting to be fetched or being fetched.
+    #[must_use]
+    pub fn is_fetching(&self, url: &str) -> bool {
+        self.pending.contains(url)
+    }
+
+    /// Whether every feed has been processed.
+    #[must_use]
+    pub const fn is_finished(&self) -> bool {
+        self.progress.is_finished()
+    }
+
+    /// Take the next finished outcome without waiting.
+    pub fn try_next(&mut self) -> Option<FetchOutcome> {
+        if self.is_finished() {
+            return None;
+        }
+        match self.rx.try_recv() {
+            Ok(outcome) => {
+                self.progress.done += 1;
+                self.pending.remove(&outcome.url);
+                Some(outcome)
+            }
+            Err(mpsc::error::TryRecvError::Disconnected) => {
+                // A fetch task died without reporting; nothing more will arrive
+                self.progress.done = self.progress.total;
+                self.pending.clear();
+                None
+            }
+            Err(mpsc::error::TryRecvError::Empty) => None,
+        }
+    }
+
+    /// Wait for the next finished outcome.
+    ///
+    /// Returns `None` once all feeds have been processed.
+    pub async fn next(&mut self) -> Option<FetchOutcome> {
+        if self.is_finished() {
+            return None;
+        }
+        let Some(outcome) = self.rx.recv().await else {
+            self.progress.done = self.progress.total;
+            self.pending.clear();
+            return None;
+        };
+        self.progress.done += 1;
+        self.pending.remove(&outcome.url);
+        Some(outcome)
+    }
+}
+
+/// Request a feed, sending conditional headers when we have them.
+///
+/// Redirects are followed here rather than by the client, so we can tell
+/// whether the feed has moved for good. Alongside the final response this
+/// returns the new URL if every hop was a permanent redirect.
+async fn send_request(
+    client: &reqwest::Client,
+    request: &FetchRequest,
+) -> Result<(reqwest::Response, Option<String>)> {
+    let origin = reqwest::Url::parse(&request.url)?;
+    let mut url = origin.clone();
+    let mut permanent = true;
+
+    for _ in 0..=MAX_REDIRECTS {
+        let mut builder = request
+            .options
+            .apply(client.get(url.clone()), http::same_origin(&origin, &url));
+        if let Some(etag) = &request.etag {
+            builder = builder.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &request.last_modified {
+            builder = builder.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let response = builder.send().await?;
+        let status = response.status();
+        let location = header_string(response.headers(), header::LOCATION);
+
+        let Some(location) = location.filter(|_| status.is_redirection()) else {
+            let moved_to = (permanent && url.as_str() != request.url).then(|| url.to_string());
+            return Ok((response, moved_to));
+        };
+
+        permanent &= matches!(
+            status,
+            StatusCode::MOVED_PERMANENTLY | StatusCode::PERMANENT_REDIRECT
+        );
+        url = url.join(&location)?;
+    }
+
+    Err(eyre!("Too many redirects"))
+}
+
+/// Turn a feed response into parsed items.
+async fn read_response(response: reqwest::Response) -> Result<FetchResponse> {
+    let status = response.status();
+
+    if status == StatusCode::NOT_MODIFIED {
+        return Ok(FetchResponse::NotModified);
+    }
+    if !status.is_success() {
+        return Err(eyre!("HTTP {status}"));
+    }
+
+    let etag = header_string(response.headers(), header::ETAG);
+    let last_modified = header_string(response.headers(), header::LAST_MODIFIED);
+
+    let bytes = response.bytes().await?;
+    let parsed = parser::parse_feed(&bytes)?;
+
+    Ok(FetchResponse::Updated {
+        items: parsed.items,
+        schedule: parsed.schedule,
+        meta: Box::new(parsed.meta),
+        etag,
+        last_modified,
+    })
+}
+
+/// Read a header as an owned string, ignoring non-ASCII values.
+fn header_string(headers: &header::HeaderMap, name: header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(ToString::to_string)
+}
+
+/// Key used to group requests by host.
+fn host_key(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_lowercase))
+        .unwrap_or_else(|| url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_key() {
+        assert_eq!(host_key("https://Example.com/feed.xml"), "example.com");
+        assert_eq!(host_key("http://example.com:8080/rss"), "example.com");
+        assert_eq!(host_key("not a url"), "not a url");
+    }
+
+    #[test]
+    fn test_progress_display() {
+        let progress = RefreshProgress {
+            done: 42,
+            total: 300,
+        };
+        assert_eq!(progress.to_string(), "42/300");
+        assert!(!progress.is_finished());
+    }
+
+    /// Serve canned responses by path on a local port.
+    async fn serve(routes: &'static [(&'static str, &'static str)]) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0; 4096];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let path = request.split_whitespace().nth(1).unwrap_or("/");
+                let response = routes.iter().find(|(p, _)| *p == path).map_or(
+                    "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\n\r\n",
+                    |(_, r)| r,
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        base
+    }
+
+    #[tokio::test]
+    async fn test_permanent_redirects_are_reported() {
+        const FEED: &str = "HTTP/1.1 200 OK\r\ncontent-length: 60\r\nconnection: close\r\n\r\n\
+            <rss version=\"2.0\"><channel><title>T</title></channel></rss>";
+        static ROUTES: [(&str, &str); 4] = [
+            (
+                "/old",
+                "HTTP/1.1 301 Moved Permanently\r\nlocation: /older\r\ncontent-length: 0\r\n\r\n",
+            ),
+            (
+                "/older",
+                "HTTP/1.1 308 Permanent Redirect\r\nlocation: /new\r\ncontent-length: 0\r\n\r\n",
+            ),
+            (
+                "/temp",
+                "HTTP/1.1 302 Found\r\nlocation: /old\r\ncontent-length: 0\r\n\r\n",
+            ),
+            ("/new", FEED),
+        ];
+        let base = serve(&ROUTES).await;
+
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+        let fetch = |path: &str| FetchRequest {
+            url: format!("{base}{path}"),
+            ..Default::default()
+        };
+
+        let (response, moved_to) = send_request(&client, &fetch("/old")).await.unwrap();
+        assert!(response.status().is_success());
+        assert_eq!(moved_to, Some(format!("{base}/new")));
+
+        // A temporary hop anywhere in the chain means the feed hasn't moved
+        let (response, moved_to) = send_request(&client, &fetch("/temp")).await.unwrap();
+        assert!(response.status().is_success());
+        assert!(moved_to.is_none());
+
+        let (_, moved_to) = send_request(&client, &fetch("/new")).await.unwrap();
+        assert!(moved_to.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_empty_job_finishes_immediately() {
+        let client = reqwest::Client::new();
+        let mut job = RefreshJob::spawn(&Ok(client), Vec::new(), RefreshLimits::default());
+        assert!(job.is_finished());
+        assert!(job.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_job_tracks_feeds_being_fetched() {
+        static ROUTES: [(&str, &str); 0] = [];
+        let base = serve(&ROUTES).await;
+        let url = format!("{base}/feed.xml");
+
+        let client = reqwest::Client::new();
+        let requests = vec![FetchRequest {
+            url: url.clone(),
+            ..Default::default()
+        }];
+        let mut job = RefreshJob::spawn(&Ok(client), requests, RefreshLimits::default());
+        assert!(job.is_fetching(&url));
+        assert!(!job.is_fetching(&format!("{base}/other.xml")));
+
+        assert_eq!(job.next().await.unwrap().url, url);
+        assert!(!job.is_fetching(&url));
+    }
+}
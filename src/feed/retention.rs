@@ -0,0 +1,119 @@
+//! Retention policy for articles that dropped out of their feed.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// How many archived articles to keep, and for how long.
+///
+/// Set globally, per folder and per feed. Unset fields fall back to the
+/// folder's settings, then to the global ones, then to the defaults.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Retention {
+    /// Most articles kept per feed, counting the ones still in it (0 = no limit).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<usize>,
+
+    /// Days an article is kept after it was first seen (0 = forever).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u32>,
+
+    /// Never drop unread articles (default: true).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_unread: Option<bool>,
+
+    /// Never drop starred articles (default: true).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_starred: Option<bool>,
+}
+
+impl Retention {
+    /// Articles kept per feed when no limit is configured.
+    pub const DEFAULT_MAX_ITEMS: usize = 500;
+
+    /// Fill unset fields from `fallback`.
+    #[must_use]
+    pub const fn or(self, fallback: Self) -> Self {
+        Self {
+            max_items: match self.max_items {
+                Some(n) => Some(n),
+                None => fallback.max_items,
+            },
+            max_age_days: match self.max_age_days {
+                Some(n) => Some(n),
+                None => fallback.max_age_days,
+            },
+            keep_unread: match self.keep_unread {
+                Some(keep) => Some(keep),
+                None => fallback.keep_unread,
+            },
+            keep_starred: match self.keep_starred {
+                Some(keep) => Some(keep),
+                None => fallback.keep_starred,
+            },
+        }
+    }
+
+    /// Whether nothing is set.
+    #[must_use]
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Most articles kept per feed, if limited.
+    #[must_use]
+    pub fn max_items(&self) -> Option<usize> {
+        match self.max_items.unwrap_or(Self::DEFAULT_MAX_ITEMS) {
+            0 => None,
+            n => Some(n),
+        }
+    }
+
+    /// Articles first seen before this are dropped, if limited.
+    #[must_use]
+    pub fn cutoff(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.max_age_days
+            .filter(|&days| days > 0)
+            .map(|days| now - Duration::days(i64::from(days)))
+    }
+
+    /// Whether an article with these flags is never dropped.
+    #[must_use]
+    pub fn keeps(&self, read: bool, starred: bool) -> bool {
+        (!read && self.keep_unread.unwrap_or(true))
+            || (starred && self.keep_starred.unwrap_or(true))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retention_layers() {
+        let global = Retention {
+            max_items: Some(100),
+            keep_unread: Some(false),
+            ..Retention::default()
+        };
+        let folder = Retention {
+            max_age_days: Some(30),
+            ..Retention::default()
+        };
+        let feed = Retention {
+            max_items: Some(0),
+            ..Retention::default()
+        };
+
+        let policy = feed.or(folder).or(global);
+        assert_eq!(policy.max_items(), None);
+        assert_eq!(policy.max_age_days, Some(30));
+        assert!(!policy.keeps(false, false));
+        assert!(policy.keeps(true, true));
+
+        let defaults = Retention::default();
+        assert_eq!(defaults.max_items(), Some(Retention::DEFAULT_MAX_ITEMS));
+        assert_eq!(defaults.cutoff(Utc::now()), None);
+        assert!(defaults.keeps(false, false));
+        assert!(!defaults.keeps(true, false));
+    }
+}
@@ -0,0 +1,340 @@
+//! Per-feed refresh scheduling.
+//!
+//! Every feed gets its own next-due time. The interval comes from a
+//! per-feed override if there is one, otherwise from how often the feed
+//! actually posts, falling back to the publisher's `sy:updatePeriod` hint
+//! and then the global refresh interval. RSS `<ttl>` acts as a floor, and
+//! `skipHours`/`skipDays` push the due time out of excluded slots.
+
+use chrono::{DateTime, Datelike, Duration, DurationRound, Timelike, Utc, Weekday};
+use quick_xml::{Reader, events::Event};
+use serde::{Deserialize, Serialize};
+
+/// Shortest interval the adaptive schedule will pick, in minutes.
+const MIN_INTERVAL: i64 = 15;
+
+/// Longest interval the adaptive schedule will pick, in minutes.
+const MAX_INTERVAL: i64 = 24 * 60;
+
+/// Longest `<ttl>` we honour, in minutes, to guard against bogus values.
+const MAX_TTL: u32 = 7 * 24 * 60;
+
+/// Number of recent posts used to estimate how often a feed updates.
+const HISTORY: usize = 10;
+
+/// Refresh hints published by the feed itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleHints {
+    /// RSS `<ttl>`: minutes the feed may be cached before refreshing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u32>,
+
+    /// `sy:updatePeriod` divided by `sy:updateFrequency`, in minutes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_period: Option<u32>,
+
+    /// RSS `skipHours`: hours (0-23, GMT) in which not to fetch.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skip_hours: Vec<u32>,
+
+    /// RSS `skipDays`: days on which not to fetch.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skip_days: Vec<Weekday>,
+}
+
+impl ScheduleHints {
+    /// Whether the feed published no hints at all.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Extract scheduling hints from raw feed XML.
+    ///
+    /// Malformed or missing elements are ignored.
+    #[must_use]
+    pub fn parse(bytes: &[u8]) -> Self {
+        let mut reader = Reader::from_reader(bytes);
+        reader.config_mut().trim_text(true);
+
+        let mut hints = Self::default();
+        let mut path: Vec<String> = Vec::new();
+        let mut period_minutes = None;
+        let mut frequency = None;
+
+        loop {
+            match reader.read_event() {
+                Ok(Event::Start(e)) => {
+                    let name = e.local_name();
+                    path.push(String::from_utf8_lossy(name.as_ref()).to_ascii_lowercase());
+                }
+                Ok(Event::End(_)) => {
+                    path.pop();
+                }
+                Ok(Event::Text(e)) => {
+                    let Ok(text) = e.decode() else { continue };
+                    let text = text.trim();
+                    let parent = path.len().checked_sub(2).and_then(|i| path.get(i));
+
+                    match (parent.map(String::as_str), path.last().map(String::as_str)) {
+                        (Some("channel"), Some("ttl")) => {
+                            hints.ttl = text.parse().ok().filter(|&t| t > 0);
+                        }
+                        (Some("channel" | "feed"), Some("updateperiod")) => {
+                            period_minutes = parse_update_period(text);
+                        }
+                        (Some("channel" | "feed"), Some("updatefrequency")) => {
+                            frequency = text.parse::<u32>().ok().filter(|&f| f > 0);
+                        }
+                        (Some("skiphours"), Some("hour")) => {
+                            // Some feeds use 24 for midnight
+                            if let Ok(hour) = text.parse::<u32>() {
+                                hints.skip_hours.push(hour % 24);
+                            }
+                        }
+                        (Some("skipdays"), Some("day")) => {
+                            if let Ok(day) = text.parse::<Weekday>() {
+                                hints.skip_days.push(day);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Ok(Event::Eof) | Err(_) => break,
+                _ => {}
+            }
+        }
+
+        hints.update_period = period_minutes.map(|p| p / frequency.unwrap_or(1).max(1));
+        hints
+    }
+
+    /// Whether fetching at `at` falls in a skipped hour or day.
+    fn skips(&self, at: DateTime<Utc>) -> bool {
+        self.skip_hours.contains(&at.hour()) || self.skip_days.contains(&at.weekday())
+    }
+}
+
+/// Convert an `sy:updatePeriod` value to minutes.
+fn parse_update_period(period: &str) -> Option<u32> {
+    match period.to_ascii_lowercase().as_str() {
+        "hourly" => Some(60),
+        "daily" => Some(24 * 60),
+        "weekly" => Some(7 * 24 * 60),
+        "monthly" => Some(30 * 24 * 60),
+        "yearly" => Some(365 * 24 * 60),
+        _ => None,
+    }
+}
+
+/// Work out how long to wait between refreshes of a feed.
+///
+/// `override_minutes` is the per-feed setting from the config, where 0
+/// disables automatic refreshing. `default_minutes` is the global interval,
+/// used when there is nothing better to go on. `published` holds the
+/// publication dates of the feed's items, in any order.
+#[must_use]
+pub fn refresh_interval(
+    hints: &ScheduleHints,
+    published: &[DateTime<Utc>],
+    override_minutes: Option<u32>,
+    default_minutes: u32,
+    now: DateTime<Utc>,
+) -> Option<Duration> {
+    if let Some(minutes) = override_minutes {
+        return (minutes > 0).then(|| Duration::minutes(i64::from(minutes)));
+    }
+
+    let interval = posting_interval(published, now)
+        .or_else(|| hints.update_period.map(|p| Duration::minutes(i64::from(p))))
+        .map_or_else(
+            || Duration::minutes(i64::from(default_minutes)),
+            |i| {
+                i.clamp(
+                    Duration::minutes(MIN_INTERVAL),
+                    Duration::minutes(MAX_INTERVAL),
+                )
+            },
+        );
+
+    // The publisher asked us not to refresh any sooner than this
+    let ttl = hints.ttl.map_or_else(Duration::zero, |t| {
+        Duration::minutes(i64::from(t.min(MAX_TTL)))
+    });
+
+    Some(interval.max(ttl))
+}
+
+/// Estimate a polling interval from recent publication dates.
+///
+/// Polls at half the typical gap between posts, so a new post is usually
+/// picked up well before the next one. The time since the newest post
+/// counts as a gap too, which lets feeds that have gone quiet back off.
+fn posting_interval(published: &[DateTime<Utc>], now: DateTime<Utc>) -> Option<Duration> {
+    let mut recent: Vec<DateTime<Utc>> = published.iter().copied().filter(|&p| p <= now).collect();
+    recent.sort_unstable_by(|a, b| b.cmp(a));
+    recent.dedup();
+    recent.truncate(HISTORY);
+
+    if recent.len() < 3 {
+        return None;
+    }
+
+    let mut gaps: Vec<Duration> = recent.windows(2).map(|w| w[0] - w[1]).collect();
+    gaps.sort_unstable();
+    let median = gaps[gaps.len() / 2];
+    let quiet = now - recent[0];
+
+    Some(median.max(quiet) / 2)
+}
+
+/// Work out when a feed is next due for a refresh.
+///
+/// Feeds that have never been checked are due immediately.
+#[must_use]
+pub fn next_due(
+    last_checked: Option<DateTime<Utc>>,
+    interval: Duration,
+    hints: &ScheduleHints,
+    now: DateTime<Utc>,
+) -> DateTime<Utc> {
+    let Some(last) = last_checked else {
+        return now;
+    };
+
+    let due = last + interval;
+    if !hints.skips(due) {
+        return due;
+    }
+
+    // Step to the start of the next hour until we leave the skipped slots
+    let mut at = due.duration_trunc(Duration::hours(1)).unwrap_or(due);
+    for _ in 0..24 * 7 {
+        at += Duration::hours(1);
+        if !hints.skips(at) {
+            return at;
+        }
+    }
+
+    // Every slot is skipped, which makes no sense; ignore the hints
+    due
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        // 2024-01-01 is a Monday
+        Utc.with_ymd_and_hms(2024, 1, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_hints() {
+        let xml = br#"<?xml version="1.0"?>
+            <rss xmlns:sy="http://purl.org/rss/1.0/modules/syndication/">
+              <channel>
+                <ttl>60</ttl>
+                <sy:updatePeriod>daily</sy:updatePeriod>
+                <sy:updateFrequency>2</sy:updateFrequency>
+                <skipHours><hour>1</hour><hour>24</hour></skipHours>
+                <skipDays><day>Sunday</day></skipDays>
+                <item><title>Hi</title><ttl>5</ttl></item>
+              </channel>
+            </rss>"#;
+
+        let hints = ScheduleHints::parse(xml);
+        assert_eq!(hints.ttl, Some(60));
+        assert_eq!(hints.update_period, Some(12 * 60));
+        assert_eq!(hints.skip_hours, vec![1, 0]);
+        assert_eq!(hints.skip_days, vec![Weekday::Sun]);
+    }
+
+    #[test]
+    fn test_override_wins() {
+        let hints = ScheduleHints {
+            ttl: Some(600),
+            ..Default::default()
+        };
+        let now = at(10, 12);
+
+        assert_eq!(
+            refresh_interval(&hints, &[], Some(5), 30, now),
+            Some(Duration::minutes(5))
+        );
+        assert_eq!(refresh_interval(&hints, &[], Some(0), 30, now), None);
+    }
+
+    #[test]
+    fn test_adapts_to_posting_rate() {
+        let now = at(10, 12);
+
+        // Hourly posts, the latest just now
+        let hourly: Vec<_> = (0..6).map(|h| now - Duration::hours(h)).collect();
+        assert_eq!(
+            refresh_interval(&ScheduleHints::default(), &hourly, None, 30, now),
+            Some(Duration::minutes(30))
+        );
+
+        // Posts months apart hit the upper bound
+        let rare: Vec<_> = (1..4).map(|m| now - Duration::days(90 * m)).collect();
+        assert_eq!(
+            refresh_interval(&ScheduleHints::default(), &rare, None, 30, now),
+            Some(Duration::minutes(MAX_INTERVAL))
+        );
+
+        // Not enough history: use the publisher's hint, then the default
+        let hints = ScheduleHints {
+            update_period: Some(120),
+            ..Default::default()
+        };
+        assert_eq!(
+            refresh_interval(&hints, &[now], None, 30, now),
+            Some(Duration::minutes(120))
+        );
+        assert_eq!(
+            refresh_interval(&ScheduleHints::default(), &[], None, 30, now),
+            Some(Duration::minutes(30))
+        );
+    }
+
+    #[test]
+    fn test_ttl_is_a_floor() {
+        let now = at(10, 12);
+        let hourly: Vec<_> = (0..6).map(|h| now - Duration::hours(h)).collect();
+        let hints = ScheduleHints {
+            ttl: Some(180),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            refresh_interval(&hints, &hourly, None, 30, now),
+            Some(Duration::minutes(180))
+        );
+    }
+
+    #[test]
+    fn test_next_due_skips_hours_and_days() {
+        let hints = ScheduleHints {
+            skip_hours: vec![13, 14],
+            skip_days: vec![Weekday::Sat],
+            ..Default::default()
+        };
+        let interval = Duration::minutes(30);
+
+        assert_eq!(next_due(None, interval, &hints, at(1, 9)), at(1, 9));
+        assert_eq!(
+            next_due(Some(at(1, 9)), interval, &hints, at(1, 9)),
+            at(1, 9) + interval
+        );
+
+        // Monday 13:15 is in a skipped hour, so wait until 15:00
+        let last = at(1, 12) + Duration::minutes(45);
+        assert_eq!(next_due(Some(last), interval, &hints, last), at(1, 15));
+
+        // Saturday 00:15 is a skipped day, so wait until Sunday
+        let last = at(5, 23) + Duration::minutes(45);
+        assert_eq!(next_due(Some(last), interval, &hints, last), at(7, 0));
+    }
+}
@@ -0,0 +1,482 @@
+//! `SQLite` storage for the feed cache.
+//!
+//! Feeds and items are stored as JSON rows, with the columns needed for
+//! lookups (feed, date, read state) alongside. Saves only write what changed
+//! since the last one, in a single transaction: flags on their own, and an
+//! item's row only when the item is new or its content changed.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+    time::Duration,
+};
+
+use color_eyre::Result;
+use rusqlite::{Connection, ErrorCode, OpenFlags, OptionalExtension, Transaction, params};
+use tracing::{info, warn};
+
+use super::cache::{CachedFeed, CachedItem};
+
+/// Schema version, kept in `PRAGMA user_version`.
+const SCHEMA_VERSION: i32 = 1;
+
+/// How long to wait for another connection to finish writing.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS feeds (
+        url TEXT PRIMARY KEY,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS items (
+        feed_url TEXT NOT NULL,
+        id TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        published INTEGER NOT NULL,
+        read INTEGER NOT NULL,
+        starred INTEGER NOT NULL,
+        changed INTEGER NOT NULL,
+        data TEXT NOT NULL,
+        PRIMARY KEY (feed_url, id)
+    );
+    CREATE INDEX IF NOT EXISTS items_by_date ON items (feed_url, published);
+    CREATE INDEX IF NOT EXISTS items_by_read ON items (feed_url, read);
+";
+
+/// What changed since the cache was last saved.
+#[derive(Debug, Default)]
+pub(super) struct Changes {
+    /// Feeds whose own data or list of items changed.
+    feeds: HashSet<String>,
+
+    /// Items whose read, changed or starred flag changed, by feed URL and item ID.
+    items: HashSet<(String, String)>,
+
+    /// Items whose content changed, to write in full, by feed URL and item ID.
+    rows: HashSet<(String, String)>,
+
+    /// Feeds to delete.
+    removed: HashSet<String>,
+}
+
+impl Changes {
+    /// Note a change to a feed or its list of items.
+    pub(super) fn feed(&mut self, url: &str) {
+        self.feeds.insert(url.to_string());
+    }
+
+    /// Note a change to an item's flags.
+    pub(super) fn item(&mut self, feed_url: &str, item_id: &str) {
+        self.items
+            .insert((feed_url.to_string(), item_id.to_string()));
+    }
+
+    /// Note a change to an item's content.
+    pub(super) fn row(&mut self, feed_url: &str, item_id: &str) {
+        self.rows
+            .insert((feed_url.to_string(), item_id.to_string()));
+    }
+
+    /// Note that a feed is gone.
+    pub(super) fn remove(&mut self, url: &str) {
+        self.feeds.remove(url);
+        self.items.retain(|(feed_url, _)| feed_url != url);
+        self.rows.retain(|(feed_url, _)| feed_url != url);
+        self.removed.insert(url.to_string());
+    }
+
+    /// Whether there is nothing to save.
+    pub(super) fn is_empty(&self) -> bool {
+        self.feeds.is_empty()
+            && self.items.is_empty()
+            && self.rows.is_empty()
+            && self.removed.is_empty()
+    }
+
+    /// Forget all changes, e.g. after saving them.
+    pub(super) fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Open the database, creating the schema if needed.
+pub(super) fn open(path: &Path) -> Result<Connection> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let conn = Connection::open(path)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+
+    check_version(&conn)?;
+    conn.execute_batch(SCHEMA)?;
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    Ok(conn)
+}
+
+/// Open an existing database for reading only, leaving the file untouched.
+pub(super) fn open_read_only(path: &Path) -> Result<Connection> {
+    let conn = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    check_version(&conn)?;
+    Ok(conn)
+}
+
+/// Refuse a database written by a newer feedo.
+fn check_version(conn: &Connection) -> Result<()> {
+    let version: i32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version > SCHEMA_VERSION {
+        return Err(color_eyre::eyre::eyre!(
+            "Cache database is from a newer version of feedo (schema {version})"
+        ));
+    }
+    Ok(())
+}
+
+/// Whether an error means the database file itself is damaged.
+///
+/// Anything else, like a locked database or one from a newer feedo, is no
+/// reason to replace it.
+pub(super) fn is_corrupt(error: &color_eyre::Report) -> bool {
+    matches!(
+        error
+            .downcast_ref::<rusqlite::Error>()
+            .and_then(rusqlite::Error::sqlite_error_code),
+        Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase)
+    )
+}
+
+/// Read all feeds and their items.
+pub(super) fn load(conn: &Connection) -> Result<HashMap<String, CachedFeed>> {
+    let mut feeds = HashMap::new();
+
+    let mut stmt = conn.prepare("SELECT url, data FROM feeds")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+    for row in rows {
+        let (url, data) = row?;
+        match serde_json::from_str::<CachedFeed>(&data) {
+            Ok(feed) => {
+                feeds.insert(url, feed);
+            }
+            Err(e) => warn!("Skipping unreadable cached feed {url}: {e}"),
+        }
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT feed_url, data, read, starred, changed FROM items ORDER BY feed_url, position",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, bool>(2)?,
+            row.get::<_, bool>(3)?,
+            row.get::<_, bool>(4)?,
+        ))
+    })?;
+    for row in rows {
+        let (feed_url, data, read, starred, changed) = row?;
+        let Some(feed) = feeds.get_mut(&feed_url) else {
+            continue;
+        };
+        match serde_json::from_str::<CachedItem>(&data) {
+            // The flag columns are updated on their own, so they win
+            Ok(item) => feed.items.push(CachedItem {
+                changed,
+                previous_body: item.previous_body.filter(|_| changed),
+                starred,
+                read,
+                ..item
+            }),
+            Err(e) => warn!("Skipping unreadable cached item in {feed_url}: {e}"),
+        }
+    }
+
+    Ok(feeds)
+}
+
+/// Write the changed feeds and items in one transaction.
+pub(super) fn save(
+    conn: &mut Connection,
+    feeds: &HashMap<String, CachedFeed>,
+    changes: &Changes,
+) -> Result<()> {
+    let tx = conn.transaction()?;
+
+    for url in &changes.removed {
+        tx.execute("DELETE FROM items WHERE feed_url = ?1", [url])?;
+        tx.execute("DELETE FROM feeds WHERE url = ?1", [url])?;
+    }
+
+    for url in &changes.feeds {
+        if let Some(feed) = feeds.get(url) {
+            write_feed(&tx, feed, &changes.rows)?;
+        }
+    }
+
+    // Changed items of feeds whose list of items is as it was
+    for (feed_url, item_id) in &changes.rows {
+        if changes.feeds.contains(feed_url) {
+            continue;
+        }
+        let item = feeds
+            .get(feed_url)
+            .and_then(|f| f.items.iter().enumerate().find(|(_, i)| &i.id == item_id));
+        if let Some((position, item)) = item {
+            write_item(&tx, feed_url, position, item)?;
+        }
+    }
+
+    {
+        let mut update = tx.prepare_cached(
+            "UPDATE items SET read = ?3, starred = ?4, changed = ?5
+             WHERE feed_url = ?1 AND id = ?2",
+        )?;
+        for (feed_url, item_id) in &changes.items {
+            let item = feeds
+                .get(feed_url)
+                .and_then(|f| f.items.iter().find(|i| &i.id == item_id));
+            if let Some(item) = item {
+                update.execute(params![
+                    feed_url,
+                    item_id,
+                    item.read,
+                    item.starred,
+                    item.changed
+                ])?;
+            }
+        }
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// Write a feed and bring its items in line with it.
+///
+/// Items in `rows` or not stored yet are written in full, items that moved
+/// only get their position updated, and items no longer in the feed are
+/// deleted. The rest are left alone.
+fn write_feed(tx: &Transaction, feed: &CachedFeed, rows: &HashSet<(String, String)>) -> Result<()> {
+    // Items get rows of their own
+    let mut data = serde_json::to_value(feed)?;
+    if let Some(object) = data.as_object_mut() {
+        object.remove("items");
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO feeds (url, data) VALUES (?1, ?2)",
+        params![feed.url, data.to_string()],
+    )?;
+
+    let mut stored: HashMap<String, i64> = tx
+        .prepare_cached("SELECT id, position FROM items WHERE feed_url = ?1")?
+        .query_map([&feed.url], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut moved =
+        tx.prepare_cached("UPDATE items SET position = ?3 WHERE feed_url = ?1 AND id = ?2")?;
+    for (position, item) in feed.items.iter().enumerate() {
+        let changed = rows.contains(&(feed.url.clone(), item.id.clone()));
+        match stored.remove(&item.id) {
+            Some(stored_at) if !changed => {
+                let position = i64::try_from(position)?;
+                if stored_at != position {
+                    moved.execute(params![feed.url, item.id, position])?;
+                }
+            }
+            _ => write_item(tx, &feed.url, position, item)?,
+        }
+    }
+
+    let mut delete = tx.prepare_cached("DELETE FROM items WHERE feed_url = ?1 AND id = ?2")?;
+    for id in stored.keys() {
+        delete.execute(params![feed.url, id])?;
+    }
+    Ok(())
+}
+
+/// Write an item's row in full.
+fn write_item(tx: &Transaction, feed_url: &str, position: usize, item: &CachedItem) -> Result<()> {
+    tx.prepare_cached(
+        "INSERT OR REPLACE INTO items
+         (feed_url, id, position, published, read, starred, changed, data)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?
+    .execute(params![
+        feed_url,
+        item.id,
+        i64::try_from(position)?,
+        item.published.unwrap_or(item.cached_at).timestamp(),
+        item.read,
+        item.starred,
+        item.changed,
+        serde_json::to_string(item)?,
+    ])?;
+    Ok(())
+}
+
+/// Write a consistent copy of the database to `dest`.
+pub(super) fn backup(conn: &Connection, dest: &Path) -> Result<()> {
+    if dest.exists() {
+        fs::remove_file(dest)?;
+    }
+    let dest = dest
+        .to_str()
+        .ok_or_else(|| color_eyre::eyre::eyre!("Backup path is not valid UTF-8"))?;
+    conn.execute("VACUUM INTO ?1", [dest])?;
+    Ok(())
+}
+
+/// Move the contents of a `cache.json` into the database, once.
+///
+/// Does nothing if the database already has feeds. The JSON file is kept,
+/// renamed to `cache.json.migrated`.
+pub(super) fn migrate_json(conn: &mut Connection, json_path: &Path) -> Result<()> {
+    if !json_path.exists() {
+        return Ok(());
+    }
+    let has_feeds = conn
+        .query_row("SELECT 1 FROM feeds LIMIT 1", [], |_| Ok(()))
+        .optional()?
+        .is_some();
+    if has_feeds {
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(json_path)?;
+    let feeds: HashMap<String, CachedFeed> = serde_json::from_str(&content)?;
+
+    let mut changes = Changes::default();
+    for url in feeds.keys() {
+        changes.feed(url);
+    }
+    save(conn, &feeds, &changes)?;
+
+    fs::rename(json_path, json_path.with_extension("json.migrated"))?;
+    info!(
+        "Migrated {} cached feeds from {}",
+        feeds.len(),
+        json_path.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_then_save_incrementally() {
+        let dir = std::env::temp_dir().join(format!("feedo-store-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let json_path = dir.join("cache.json");
+        let url = "https://example.com/feed.xml";
+
+        let item = |title: &str| {
+            serde_json::json!({
+                "id": CachedItem::generate_id(None, None, title),
+                "title": title,
+                "link": null,
+                "published": null,
+                "summary": null,
+                "read": false,
+                "cached_at": "2024-01-01T00:00:00Z",
+            })
+        };
+        let json = serde_json::json!({
+            url: {
+                "url": url,
+                "name": "Example",
+                "items": [item("One"), item("Two")],
+                "last_fetched": null,
+                "etag": "\"abc\"",
+            }
+        });
+        fs::write(&json_path, json.to_string()).unwrap();
+
+        let mut conn = open(&dir.join("cache.db")).unwrap();
+        migrate_json(&mut conn, &json_path).unwrap();
+        assert!(!json_path.exists());
+        assert!(dir.join("cache.json.migrated").exists());
+
+        let mut loaded = load(&conn).unwrap();
+        assert_eq!(loaded[url].etag.as_deref(), Some("\"abc\""));
+        let titles: Vec<&str> = loaded[url].items.iter().map(|i| i.title.as_str()).collect();
+        assert_eq!(titles, ["One", "Two"]);
+
+        // Only the flag is written; the rest of the row stays as it was
+        loaded.get_mut(url).unwrap().items[1].read = true;
+        let mut changes = Changes::default();
+        changes.item(url, &loaded[url].items[1].id);
+        save(&mut conn, &loaded, &changes).unwrap();
+        let reloaded = load(&conn).unwrap();
+        assert!(!reloaded[url].items[0].read);
+        assert!(reloaded[url].items[1].read);
+
+        // Rewriting the feed leaves the rows of untouched items alone
+        let data = |conn: &Connection, id: &str| -> String {
+            conn.query_row(
+                "SELECT data FROM items WHERE feed_url = ?1 AND id = ?2",
+                params![url, id],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        let (one, two) = (
+            reloaded[url].items[0].id.clone(),
+            reloaded[url].items[1].id.clone(),
+        );
+        conn.execute(
+            "UPDATE items SET data = json_set(data, '$.summary', 'untouched') WHERE id = ?1",
+            [&one],
+        )
+        .unwrap();
+        let mut edited = reloaded;
+        let feed = edited.get_mut(url).unwrap();
+        feed.etag = Some("\"def\"".to_string());
+        feed.items[1].summary = Some("edited".to_string());
+        let mut changes = Changes::default();
+        changes.feed(url);
+        changes.row(url, &two);
+        save(&mut conn, &edited, &changes).unwrap();
+        assert!(data(&conn, &one).contains("untouched"));
+        assert!(data(&conn, &two).contains("edited"));
+        let reloaded = load(&conn).unwrap();
+        assert_eq!(reloaded[url].etag.as_deref(), Some("\"def\""));
+
+        // Items that left the feed are deleted, the rest move up
+        let mut edited = reloaded;
+        edited.get_mut(url).unwrap().items.remove(0);
+        let mut changes = Changes::default();
+        changes.feed(url);
+        save(&mut conn, &edited, &changes).unwrap();
+        let reloaded = load(&conn).unwrap();
+        let titles: Vec<&str> = reloaded[url]
+            .items
+            .iter()
+            .map(|i| i.title.as_str())
+            .collect();
+        assert_eq!(titles, ["Two"]);
+
+        // Removed feeds go with their items
+        let mut changes = Changes::default();
+        changes.remove(url);
+        save(&mut conn, &reloaded, &changes).unwrap();
+        assert!(load(&conn).unwrap().is_empty());
+        let items: i64 = conn
+            .query_row("SELECT COUNT(*) FROM items", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(items, 0);
+
+        drop(conn);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
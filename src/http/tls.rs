@@ -0,0 +1,305 @@
+//! TLS settings for self-hosted servers.
+//!
+//! Extra CA certificates are trusted on top of the platform's roots. A host
+//! can also be pinned to the SHA-256 fingerprint of its certificate, which
+//! then replaces normal verification for that host, so self-signed
+//! certificates work.
+//!
+//! A CA file that can't be read is skipped and a host with a malformed pin
+//! is refused, so a mistake only breaks the connections that depend on it.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, eyre},
+};
+use reqwest::ClientBuilder;
+use rustls::{
+    DigitallySignedStruct, SignatureScheme,
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{CryptoProvider, verify_tls12_signature, verify_tls13_signature},
+    pki_types::{CertificateDer, ServerName, UnixTime, pem::PemObject},
+};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::config::Config;
+
+/// Extra trusted certificates and pinned hosts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsSettings {
+    /// PEM files with CA certificates to trust.
+    pub ca_certificates: Vec<PathBuf>,
+
+    /// SHA-256 certificate fingerprints, by host name.
+    pub pinned: BTreeMap<String, String>,
+}
+
+impl TlsSettings {
+    /// The TLS settings from the config.
+    #[must_use]
+    pub fn global(config: &Config) -> Self {
+        Self {
+            ca_certificates: config.ca_certificates.clone(),
+            pinned: config.pinned_certificates.clone(),
+        }
+    }
+
+    /// Whether the platform defaults are used unchanged.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ca_certificates.is_empty() && self.pinned.is_empty()
+    }
+
+    /// Problems with the settings, as the config setting and a message.
+    #[must_use]
+    pub fn problems(&self) -> Vec<(String, String)> {
+        let files = self
+            .ca_certificates
+            .iter()
+            .enumerate()
+            .filter_map(|(i, path)| {
+                read_pem(path)
+                    .err()
+                    .map(|e| (format!("ca_certificates[{i}]"), format!("{e:#}")))
+            });
+        let pins = self
+            .pinned
+            .iter()
+            .filter(|(_, pin)| parse_fingerprint(pin).is_none())
+            .map(|(host, _)| {
+                (
+                    "pinned_certificates".to_string(),
+                    format!("invalid pin for {host}: expected a SHA-256 fingerprint"),
+                )
+            });
+        files.chain(pins).collect()
+    }
+
+    /// Apply these settings to a client.
+    pub(super) fn apply(&self, builder: ClientBuilder) -> Result<ClientBuilder> {
+        if self.is_empty() {
+            return Ok(builder);
+        }
+
+        let roots = self.load_roots();
+        if self.pinned.is_empty() {
+            let certs = roots
+                .iter()
+                .map(|c| reqwest::Certificate::from_der(c))
+                .collect::<reqwest::Result<Vec<_>>>()?;
+            return Ok(builder.tls_certs_merge(certs));
+        }
+
+        let pins = self
+            .pinned
+            .iter()
+            .map(|(host, pin)| {
+                let fingerprint = parse_fingerprint(pin);
+                if fingerprint.is_none() {
+                    warn!("Invalid certificate pin for {host}; connections to it will fail");
+                }
+                (host.to_ascii_lowercase(), fingerprint)
+            })
+            .collect();
+
+        let provider = CryptoProvider::get_default()
+            .cloned()
+            .unwrap_or_else(|| Arc::new(rustls::crypto::aws_lc_rs::default_provider()));
+        let verifier = PinningVerifier {
+            inner: rustls_platform_verifier::Verifier::new_with_extra_roots(
+                roots,
+                Arc::clone(&provider),
+            )?,
+            pins,
+            provider: Arc::clone(&provider),
+        };
+        let tls = rustls::ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth();
+
+        Ok(builder.tls_backend_preconfigured(tls))
+    }
+
+    /// Read every certificate from the configured PEM files that can be read.
+    fn load_roots(&self) -> Vec<CertificateDer<'static>> {
+        let mut roots = Vec::new();
+        for path in &self.ca_certificates {
+            match read_pem(path) {
+                Ok(certs) => roots.extend(certs),
+                Err(e) => warn!("Skipping CA certificate: {e}"),
+            }
+        }
+        roots
+    }
+}
+
+/// Read the certificates in a PEM file.
+fn read_pem(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let pem = std::fs::read(path)
+        .wrap_err_with(|| format!("Failed to read CA certificate {}", path.display()))?;
+    let certs = CertificateDer::pem_slice_iter(&pem)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| eyre!("Invalid CA certificate {}: {e}", path.display()))?;
+    if certs.is_empty() {
+        return Err(eyre!("No certificates found in {}", path.display()));
+    }
+    Ok(certs)
+}
+
+/// Parse a SHA-256 fingerprint, with or without colons and a `sha256:` prefix.
+fn parse_fingerprint(pin: &str) -> Option<[u8; 32]> {
+    let pin = pin.trim();
+    let pin = pin
+        .get(..7)
+        .filter(|p| p.eq_ignore_ascii_case("sha256:"))
+        .map_or(pin, |_| &pin[7..]);
+    let hex: Vec<u8> = pin.bytes().filter(|&b| b != b':').collect();
+    if hex.len() != 64 {
+        return None;
+    }
+
+    let mut out = [0; 32];
+    for (byte, pair) in out.iter_mut().zip(hex.chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(out)
+}
+
+/// Checks pinned hosts by fingerprint and everything else with the platform verifier.
+#[derive(Debug)]
+struct PinningVerifier {
+    inner: rustls_platform_verifier::Verifier,
+    /// Fingerprints by host; `None` if the configured pin is malformed.
+    pins: HashMap<String, Option<[u8; 32]>>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinningVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let host = server_name.to_str().to_ascii_lowercase();
+        let Some(pin) = self.pins.get(&host) else {
+            return self.inner.verify_server_cert(
+                end_entity,
+                intermediates,
+                server_name,
+                ocsp_response,
+                now,
+            );
+        };
+
+        let Some(pin) = pin else {
+            return Err(rustls::Error::General(format!(
+                "the pinned fingerprint for {host} is invalid"
+            )));
+        };
+        if Sha256::digest(end_entity).as_slice() == pin {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(format!(
+                "certificate for {host} does not match the pinned fingerprint"
+            )))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use super::*;
+
+    fn fingerprint(cert: &[u8]) -> String {
+        let mut out = String::new();
+        for (i, byte) in Sha256::digest(cert).iter().enumerate() {
+            if i > 0 {
+                out.push(':');
+            }
+            let _ = write!(out, "{byte:02X}");
+        }
+        out
+    }
+
+    #[test]
+    fn test_parse_fingerprint() {
+        let cert = b"not really a certificate";
+        let expected: [u8; 32] = Sha256::digest(cert).into();
+
+        let pin = fingerprint(cert);
+        assert_eq!(pin.len(), 32 * 3 - 1);
+        assert_eq!(parse_fingerprint(&pin), Some(expected));
+        assert_eq!(
+            parse_fingerprint(&format!("sha256:{}", pin.replace(':', "").to_lowercase())),
+            Some(expected)
+        );
+        assert_eq!(parse_fingerprint("AB:CD"), None);
+    }
+
+    #[test]
+    fn test_missing_ca_file() {
+        let tls = TlsSettings {
+            ca_certificates: vec![PathBuf::from("/nonexistent/ca.pem")],
+            ..Default::default()
+        };
+        assert_eq!(tls.problems()[0].0, "ca_certificates[0]");
+        assert!(tls.apply(reqwest::Client::builder()).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_pin() {
+        let tls = TlsSettings {
+            pinned: BTreeMap::from([("rss.example.com".to_string(), "nope".to_string())]),
+            ..Default::default()
+        };
+        assert_eq!(tls.problems().len(), 1);
+        assert!(tls.apply(reqwest::Client::builder()).is_ok());
+    }
+}
@@ -39,9 +39,14 @@
 //! ## Modules
 //!
 //! - [`app`] — Main application state and event loop
+//! - [`backup`] — Full backup and restore of config and cache
 //! - [`config`] — Configuration management and persistence
+//! - [`download`] — Enclosure downloads and playback
 //! - [`feed`] — Feed fetching, parsing, and state management
+//! - [`http`] — Per-feed HTTP request settings
+//! - [`lock`] — Single-instance lock on the data directory
 //! - [`opml`] — OPML import/export for feed migration
+//! - [`persist`] — Atomic writes, backups and recovery of data files
 //! - [`sync`] — Sync with `FreshRSS`, `Miniflux` via Google Reader API
 //! - [`ui`] — Terminal UI rendering and input handling
 //!
@@ -94,11 +99,16 @@
 #![allow(clippy::missing_const_for_fn)]
 
 pub mod app;
+pub mod backup;
 pub mod config;
 pub mod credentials;
+pub mod download;
 pub mod error_report;
 pub mod feed;
+pub mod http;
+pub mod lock;
 pub mod opml;
+pub mod persist;
 pub mod sync;
 pub mod ui;
 pub mod update;
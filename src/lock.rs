@@ -0,0 +1,92 @@
+//! Single-instance lock on the data directory.
+//!
+//! Only one feedo may write the config and cache at a time. The lock is an
+//! OS file lock on `feedo.lock`, so it goes away with the process, even if it
+//! crashes.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Read, Seek, Write},
+    path::Path,
+};
+
+use color_eyre::Result;
+use fs4::{FileExt, TryLockError};
+
+/// Name of the lock file in the data directory.
+const LOCK_FILE: &str = "feedo.lock";
+
+/// Holds the lock until dropped.
+#[derive(Debug)]
+pub struct InstanceLock {
+    _file: File,
+}
+
+/// Outcome of trying to take the lock.
+#[derive(Debug)]
+pub enum Lock {
+    /// This process holds the lock.
+    Acquired(InstanceLock),
+    /// Another process holds it; its PID, if it wrote one.
+    Held(Option<u32>),
+}
+
+impl InstanceLock {
+    /// Try to take the lock in `dir`, without waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock file cannot be created or locked for a
+    /// reason other than another instance holding it.
+    pub fn acquire(dir: &Path) -> Result<Lock> {
+        fs::create_dir_all(dir)?;
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(dir.join(LOCK_FILE))?;
+
+        match FileExt::try_lock(&file) {
+            Ok(()) => {
+                // Let the other instance say who is holding it
+                file.set_len(0)?;
+                file.rewind()?;
+                write!(file, "{}", std::process::id())?;
+                file.flush()?;
+                Ok(Lock::Acquired(Self { _file: file }))
+            }
+            Err(TryLockError::WouldBlock) => {
+                let mut pid = String::new();
+                let _ = file.read_to_string(&mut pid);
+                Ok(Lock::Held(pid.trim().parse().ok()))
+            }
+            Err(TryLockError::Error(e)) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_instance_is_refused() {
+        let dir = std::env::temp_dir().join(format!("feedo-lock-{}", std::process::id()));
+
+        let Lock::Acquired(lock) = InstanceLock::acquire(&dir).unwrap() else {
+            panic!("first lock should succeed");
+        };
+        match InstanceLock::acquire(&dir).unwrap() {
+            Lock::Held(pid) => assert_eq!(pid, Some(std::process::id())),
+            Lock::Acquired(_) => panic!("lock taken twice"),
+        }
+
+        drop(lock);
+        assert!(matches!(
+            InstanceLock::acquire(&dir).unwrap(),
+            Lock::Acquired(_)
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
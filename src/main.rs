@@ -6,6 +6,10 @@ use std::path::{Path, PathBuf};
 use color_eyre::Result;
 use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
 
+use feedo::backup::{Backup, RestoreMode};
+use feedo::config::ConfigFormat;
+use feedo::http::ClientSettings;
+use feedo::lock::{InstanceLock, Lock};
 use feedo::{App, Config, GReaderClient, SyncConfig, SyncProvider};
 
 #[tokio::main]
@@ -24,6 +28,9 @@ async fn main() -> Result<()> {
         Command::Run => run_tui().await,
         Command::Import(path) => import_opml(&path),
         Command::Export(path) => export_opml(&path),
+        Command::Backup(path) => backup(&path),
+        Command::Restore(path, mode) => restore(&path, mode),
+        Command::ConfigConvert(to) => convert_config(to),
         Command::Sync => sync_feeds().await,
         Command::SyncLogin {
             server,
@@ -32,6 +39,11 @@ async fn main() -> Result<()> {
             provider,
         } => sync_login(&server, &username, &password, provider).await,
         Command::SyncStatus => sync_status().await,
+        Command::Auth {
+            url,
+            username,
+            password,
+        } => feed_auth(&url, &username, &password),
         Command::Update => run_update().await,
         Command::Help => {
             print_help();
@@ -49,6 +61,9 @@ enum Command {
     Run,
     Import(PathBuf),
     Export(PathBuf),
+    Backup(PathBuf),
+    Restore(PathBuf, RestoreMode),
+    ConfigConvert(Option<ConfigFormat>),
     Sync,
     SyncLogin {
         server: String,
@@ -57,13 +72,20 @@ enum Command {
         provider: SyncProvider,
     },
     SyncStatus,
+    Auth {
+        url: String,
+        username: String,
+        password: String,
+    },
     Update,
     Help,
     Version,
 }
 
 fn parse_args() -> Result<Command> {
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+
+    take_profile(&mut args)?;
 
     if args.len() == 1 {
         return Ok(Command::Run);
@@ -84,6 +106,14 @@ fn parse_args() -> Result<Command> {
                 .ok_or_else(|| color_eyre::eyre::eyre!("Missing output file path"))?;
             Ok(Command::Export(PathBuf::from(path)))
         }
+        "backup" => {
+            let path = args.get(2).ok_or_else(|| {
+                color_eyre::eyre::eyre!("Missing backup file path\nUsage: feedo backup <file>")
+            })?;
+            Ok(Command::Backup(PathBuf::from(path)))
+        }
+        "restore" => parse_restore(&args[2..]),
+        "config" => parse_config(&args[2..]),
         "sync" => {
             if args.len() > 2 {
                 match args[2].as_str() {
@@ -129,6 +159,30 @@ fn parse_args() -> Result<Command> {
                 Ok(Command::Sync)
             }
         }
+        "auth" => {
+            // Parse: feedo auth <feed-url> <username> <password>
+            let url = args
+                .get(2)
+                .ok_or_else(|| {
+                    color_eyre::eyre::eyre!(
+                        "Missing feed URL\nUsage: feedo auth <feed-url> <username> <password>"
+                    )
+                })?
+                .clone();
+            let username = args
+                .get(3)
+                .ok_or_else(|| color_eyre::eyre::eyre!("Missing username"))?
+                .clone();
+            let password = args
+                .get(4)
+                .ok_or_else(|| color_eyre::eyre::eyre!("Missing password"))?
+                .clone();
+            Ok(Command::Auth {
+                url,
+                username,
+                password,
+            })
+        }
         "update" => Ok(Command::Update),
         other => Err(color_eyre::eyre::eyre!(
             "Unknown option: {other}\nRun 'feedo --help' for usage"
@@ -136,6 +190,64 @@ fn parse_args() -> Result<Command> {
     }
 }
 
+/// Handle `--profile <name>`, which may come before or after the command,
+/// else `FEEDO_PROFILE`.
+fn take_profile(args: &mut Vec<String>) -> Result<()> {
+    if let Some(i) = args
+        .iter()
+        .position(|a| a == "--profile" || a.starts_with("--profile="))
+    {
+        let flag = args.remove(i);
+        let name = match flag.strip_prefix("--profile=") {
+            Some(name) => name.to_string(),
+            None if i < args.len() => args.remove(i),
+            None => return Err(color_eyre::eyre::eyre!("Missing profile name")),
+        };
+        Config::set_profile(&name)?;
+    } else if let Some(name) = std::env::var_os("FEEDO_PROFILE").filter(|n| !n.is_empty()) {
+        Config::set_profile(&name.to_string_lossy())?;
+    }
+    Ok(())
+}
+
+/// Parse: feedo config convert [json|toml]
+fn parse_config(args: &[String]) -> Result<Command> {
+    if args.first().map(String::as_str) != Some("convert") {
+        return Err(color_eyre::eyre::eyre!(
+            "Usage: feedo config convert [json|toml]"
+        ));
+    }
+    let to = args
+        .get(1)
+        .map(|name| {
+            ConfigFormat::from_name(name).ok_or_else(|| {
+                color_eyre::eyre::eyre!("Unknown config format: {name} (json or toml)")
+            })
+        })
+        .transpose()?;
+    Ok(Command::ConfigConvert(to))
+}
+
+/// Parse: feedo restore <file> [--merge|--replace]
+fn parse_restore(args: &[String]) -> Result<Command> {
+    let mut path = None;
+    let mut mode = RestoreMode::default();
+    for arg in args {
+        match arg.as_str() {
+            "--merge" => mode = RestoreMode::Merge,
+            "--replace" => mode = RestoreMode::Replace,
+            other if path.is_none() => path = Some(PathBuf::from(other)),
+            other => return Err(color_eyre::eyre::eyre!("Unexpected argument: {other}")),
+        }
+    }
+    let path = path.ok_or_else(|| {
+        color_eyre::eyre::eyre!(
+            "Missing backup file path\nUsage: feedo restore <file> [--merge|--replace]"
+        )
+    })?;
+    Ok(Command::Restore(path, mode))
+}
+
 fn print_help() {
     let config_path =
         Config::config_path().map_or_else(|| "Unknown".to_string(), |p| p.display().to_string());
@@ -150,6 +262,7 @@ USAGE:
     feedo [COMMAND]
 
 OPTIONS:
+    --profile <NAME>       Use a separate profile (or set FEEDO_PROFILE)
     -i, --import <FILE>    Import feeds from OPML file
     -e, --export <FILE>    Export feeds to OPML file
     -h, --help             Show this help message
@@ -157,9 +270,13 @@ OPTIONS:
 
 COMMANDS:
     update                                 Check for updates and install
+    backup <file>                          Save config, feeds and read state to a file
+    restore <file> [--merge|--replace]     Restore a backup (merges by default)
+    config convert [json|toml]             Switch the config file between JSON and TOML
     sync                                   Sync with configured server
     sync login <server> <user> <pw>        Configure sync server
     sync status                            Show sync configuration
+    auth <feed-url> <user> <pw>            Set HTTP Basic auth for a feed
 
     Supported sync providers: FreshRSS, Miniflux, Inoreader, The Old Reader
 
@@ -203,7 +320,22 @@ async fn run_tui() -> Result<()> {
     app.run().await
 }
 
+/// Take the data directory lock for a command that saves, or fail if the
+/// app is running.
+fn lock_data_dir() -> Result<InstanceLock> {
+    let dir = Config::data_dir()
+        .ok_or_else(|| color_eyre::eyre::eyre!("Could not determine data directory"))?;
+    match InstanceLock::acquire(&dir)? {
+        Lock::Acquired(lock) => Ok(lock),
+        Lock::Held(pid) => Err(color_eyre::eyre::eyre!(
+            "feedo is already running{}; quit it first",
+            pid.map_or_else(String::new, |pid| format!(" (pid {pid})"))
+        )),
+    }
+}
+
 fn import_opml(path: &Path) -> Result<()> {
+    let _lock = lock_data_dir()?;
     let mut config = Config::load()?;
     let count = feedo::opml::import(path, &mut config)?;
     config.save()?;
@@ -212,12 +344,86 @@ fn import_opml(path: &Path) -> Result<()> {
 }
 
 fn export_opml(path: &Path) -> Result<()> {
-    let config = Config::load()?;
+    let config = Config::load_read_only()?;
     feedo::opml::export(&config, path)?;
     println!("(◕ᴥ◕) Exported feeds to {}", path.display());
     Ok(())
 }
 
+fn backup(path: &Path) -> Result<()> {
+    // Reads only, so it works while the app is open and never writes the data
+    let config = Config::load_read_only()?;
+    let cache = feedo::FeedCache::load_read_only()?;
+    let backup = Backup::new(&config, &cache);
+    backup.write(path)?;
+    println!(
+        "(◕ᴥ◕) Backed up {} feeds and {} articles to {}",
+        config.total_feeds(),
+        backup.feeds.iter().map(|f| f.items.len()).sum::<usize>(),
+        path.display()
+    );
+    Ok(())
+}
+
+fn restore(path: &Path, mode: RestoreMode) -> Result<()> {
+    let _lock = lock_data_dir()?;
+    let backup = Backup::read(path)?;
+    println!(
+        "(◕ᴥ◕) Restoring backup from {} (feedo {})...\n",
+        backup.created.format("%Y-%m-%d %H:%M"),
+        backup.feedo_version
+    );
+
+    let mut config = Config::load()?;
+    let mut cache = feedo::FeedCache::load()?;
+    let summary = backup.restore(&mut config, &mut cache, mode);
+    config.save()?;
+    cache.save()?;
+
+    println!("✓ Added {} feeds", summary.feeds_added);
+    println!(
+        "✓ Restored {} articles in {} feeds",
+        summary.items, summary.feeds_restored
+    );
+    println!(
+        "\nPasswords are not part of backups; set them again with 'feedo auth' or 'feedo sync login'."
+    );
+    println!(
+        "Custom header values and proxy usernames and passwords aren't either; add them back to the config."
+    );
+    Ok(())
+}
+
+fn convert_config(to: Option<ConfigFormat>) -> Result<()> {
+    let _lock = lock_data_dir()?;
+    let config = Config::load()?;
+    let from = Config::config_path().map_or(ConfigFormat::Json, |p| ConfigFormat::of(&p));
+    let (path, old) = config.convert(to.unwrap_or_else(|| from.other()))?;
+    println!("(◕ᴥ◕) Config is now {}", path.display());
+    println!("  The old file was kept as {}", old.display());
+    Ok(())
+}
+
+fn feed_auth(url: &str, username: &str, password: &str) -> Result<()> {
+    let _lock = lock_data_dir()?;
+    let mut config = Config::load()?;
+    let feed = config
+        .feed_mut(url)
+        .ok_or_else(|| color_eyre::eyre::eyre!("No feed with URL {url}"))?;
+
+    if feed.set_credentials(username, password) {
+        println!("✓ Credentials encrypted and stored");
+    } else {
+        println!("⚠ Could not encrypt credentials");
+        println!("  Password will be stored in config file (not recommended)");
+    }
+    let name = feed.name.clone();
+
+    config.save()?;
+    println!("(◕ᴥ◕) {name} will now use HTTP Basic auth");
+    Ok(())
+}
+
 async fn sync_login(
     server: &str,
     username: &str,
@@ -227,7 +433,9 @@ async fn sync_login(
     println!("(◕ᴥ◕) Connecting to {server}...");
 
     // Test the connection
-    let client = GReaderClient::new(server);
+    let _lock = lock_data_dir()?;
+    let mut config = Config::load()?;
+    let client = GReaderClient::with_settings(server, &ClientSettings::global(&config))?;
     let auth = client.login(username, password).await?;
 
     // Verify by fetching user info
@@ -251,7 +459,6 @@ async fn sync_login(
     }
 
     // Save to config (credentials only stored if encryption failed)
-    let mut config = Config::load()?;
     config.sync = Some(SyncConfig {
         provider,
         server: server.to_string(),
@@ -287,7 +494,7 @@ fn get_sync_credentials(sync: &SyncConfig) -> Option<(String, String)> {
 }
 
 async fn sync_status() -> Result<()> {
-    let config = Config::load()?;
+    let config = Config::load_read_only()?;
 
     if let Some(sync) = &config.sync {
         println!("(◕ᴥ◕) Sync Configuration\n");
@@ -315,7 +522,8 @@ async fn sync_status() -> Result<()> {
         // Try to connect and show stats
         if let Some((username, password)) = credentials {
             println!("\nTesting connection...");
-            let client = GReaderClient::new(&sync.server);
+            let client =
+                GReaderClient::with_settings(&sync.server, &ClientSettings::global(&config))?;
             match client.login(&username, &password).await {
                 Ok(auth) => {
                     println!("✓ Connection successful");
@@ -345,6 +553,7 @@ async fn sync_status() -> Result<()> {
 }
 
 async fn sync_feeds() -> Result<()> {
+    let _lock = lock_data_dir()?;
     let mut config = Config::load()?;
 
     let sync = config.sync.clone().ok_or_else(|| {
@@ -361,7 +570,9 @@ async fn sync_feeds() -> Result<()> {
     let mut cache = feedo::FeedCache::load()?;
 
     // Connect and run full sync
-    let manager = feedo::SyncManager::connect(&sync.server, &username, &password).await?;
+    let settings = ClientSettings::global(&config);
+    let manager =
+        feedo::SyncManager::connect(&sync.server, &username, &password, &settings).await?;
     let result = manager.full_sync(&mut config, &mut cache).await?;
 
     // Save changes
@@ -408,7 +619,10 @@ async fn run_update() -> Result<()> {
     println!("  Current version: {}", feedo::update::VERSION);
 
     // Use crates.io API (no rate limits, more reliable)
-    let check = check_for_updates_crates_io().await;
+    let settings = Config::load_read_only()
+        .map(|c| ClientSettings::global(&c))
+        .unwrap_or_default();
+    let check = check_for_updates_crates_io(&settings).await;
 
     match check {
         VersionCheck::UpdateAvailable { latest, .. } => {
@@ -15,6 +15,8 @@ use crate::config::{Config, FeedConfig, FolderConfig};
 
 /// Import feeds from an OPML file.
 ///
+/// Feeds that are already subscribed are skipped. Returns how many were added.
+///
 /// # Errors
 ///
 /// Returns an error if the file cannot be read or parsed.
@@ -26,11 +28,15 @@ pub fn import(path: &Path, config: &mut Config) -> Result<usize> {
 
     for outline in outlines {
         if let Some(url) = &outline.xml_url {
+            if config.has_feed(url) {
+                continue;
+            }
             // Root-level feed
             config.feeds.push(FeedConfig {
                 name: outline.title.clone(),
                 url: url.clone(),
                 sync_id: None,
+                ..Default::default()
             });
             imported += 1;
         } else if !outline.children.is_empty() {
@@ -38,11 +44,18 @@ pub fn import(path: &Path, config: &mut Config) -> Result<usize> {
             let folder_feeds: Vec<FeedConfig> = outline
                 .children
                 .iter()
+                .filter(|child| {
+                    child
+                        .xml_url
+                        .as_ref()
+                        .is_some_and(|url| !config.has_feed(url))
+                })
                 .filter_map(|child| {
                     child.xml_url.as_ref().map(|url| FeedConfig {
                         name: child.title.clone(),
                         url: url.clone(),
                         sync_id: None,
+                        ..Default::default()
                     })
                 })
                 .collect();
@@ -54,6 +67,7 @@ pub fn import(path: &Path, config: &mut Config) -> Result<usize> {
                     name: outline.title,
                     icon: None,
                     expanded: true,
+                    retention: crate::feed::Retention::default(),
                     feeds: folder_feeds,
                 });
             }
@@ -191,10 +205,8 @@ fn parse_outline_attrs(e: &BytesStart) -> OpmlOutline {
         let value = attr.unescape_value().unwrap_or_default().to_string();
 
         match key.to_lowercase().as_str() {
-            "title" | "text" => {
-                if title.is_empty() {
-                    title = value;
-                }
+            "title" | "text" if title.is_empty() => {
+                title = value;
             }
             "xmlurl" => xml_url = Some(value),
             _ => {}
@@ -0,0 +1,205 @@
+//! Crash-safe file writes, rotating backups and recovery from corrupt files.
+//!
+//! Backups sit next to the file as `<name>.bak.1` (newest) to `<name>.bak.N`.
+//! A file that is corrupt is moved aside to `<name>.corrupt-<time>` and
+//! replaced by its newest backup that loads. Other load errors, like a typo
+//! in a config file, are returned and leave the file alone.
+
+use std::{
+    ffi::OsString,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+use color_eyre::{Report, Result};
+use tracing::warn;
+
+/// Number of backups kept per file.
+pub const BACKUPS: usize = 3;
+
+/// Write a file so that it either keeps its old contents or has the new ones.
+///
+/// The data goes to a temporary file in the same directory, is flushed to
+/// disk, and then renamed over the target.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let tmp = with_suffix(path, ".tmp");
+    let result = (|| {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result?;
+
+    // Make the rename itself durable; not possible on every platform
+    if let Some(dir) = path.parent()
+        && let Ok(dir) = fs::File::open(dir)
+    {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// Make room for a new backup of `path` and return where it should go.
+///
+/// Existing backups move one place down and the oldest beyond [`BACKUPS`]
+/// is deleted.
+pub fn next_backup(path: &Path) -> io::Result<PathBuf> {
+    for n in (1..BACKUPS).rev() {
+        let from = backup_path(path, n);
+        if from.exists() {
+            fs::rename(&from, backup_path(path, n + 1))?;
+        }
+    }
+    Ok(backup_path(path, 1))
+}
+
+/// Existing backups of `path`, newest first.
+#[must_use]
+pub fn backups(path: &Path) -> Vec<PathBuf> {
+    (1..=BACKUPS)
+        .map(|n| backup_path(path, n))
+        .filter(|p| p.exists())
+        .collect()
+}
+
+/// Load a file, falling back to its backups if it is corrupt.
+///
+/// If `load` fails on an existing file with an error `is_corrupt` accepts,
+/// the file is moved aside and each backup is tried in turn, newest first.
+/// The first that loads is copied into place. Fails if there is no usable
+/// backup; the corrupt file is kept either way. Any other error is returned
+/// as is.
+pub fn load_or_restore<T>(
+    path: &Path,
+    load: impl Fn(&Path) -> Result<T>,
+    is_corrupt: impl Fn(&Report) -> bool,
+) -> Result<T> {
+    let error = match load(path) {
+        Ok(value) => return Ok(value),
+        Err(e) if !path.exists() || !is_corrupt(&e) => return Err(e),
+        Err(e) => e,
+    };
+
+    let aside = quarantine(path)?;
+    warn!(
+        "{} is unreadable ({error}); moved it to {}",
+        path.display(),
+        aside.display()
+    );
+
+    for backup in backups(path) {
+        fs::copy(&backup, path)?;
+        match load(path) {
+            Ok(value) => {
+                warn!("Restored {} from {}", path.display(), backup.display());
+                return Ok(value);
+            }
+            Err(e) => {
+                warn!("Backup {} is unusable too: {e}", backup.display());
+                quarantine(path)?;
+            }
+        }
+    }
+
+    Err(error.wrap_err(format!(
+        "{} is corrupt and there is no usable backup; it was moved to {}",
+        path.display(),
+        aside.display()
+    )))
+}
+
+/// Move a file out of the way, along with `SQLite`'s `-wal` and `-shm` files.
+fn quarantine(path: &Path) -> io::Result<PathBuf> {
+    let stamp = Utc::now().format("%Y%m%d%H%M%S");
+    let mut aside = with_suffix(path, &format!(".corrupt-{stamp}"));
+    for n in 2.. {
+        if !aside.exists() {
+            break;
+        }
+        aside = with_suffix(path, &format!(".corrupt-{stamp}-{n}"));
+    }
+    fs::rename(path, &aside)?;
+
+    for sidecar in ["-wal", "-shm"] {
+        let from = with_suffix(path, sidecar);
+        if from.exists() {
+            fs::rename(&from, with_suffix(&aside, sidecar))?;
+        }
+    }
+    Ok(aside)
+}
+
+fn backup_path(path: &Path, n: usize) -> PathBuf {
+    with_suffix(path, &format!(".bak.{n}"))
+}
+
+/// `path` with `suffix` appended to its file name.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore_from_newest_good_backup() {
+        let dir = std::env::temp_dir().join(format!("feedo-persist-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.json");
+        let parse = |p: &Path| -> Result<u32> {
+            let content = fs::read_to_string(p)?;
+            if content.is_empty() {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            Ok(content.trim().parse()?)
+        };
+        let truncated = |e: &Report| {
+            e.downcast_ref::<io::Error>()
+                .is_some_and(|e| e.kind() == io::ErrorKind::UnexpectedEof)
+        };
+
+        for version in ["1", "2", "3", "4"] {
+            if path.exists() {
+                fs::copy(&path, next_backup(&path).unwrap()).unwrap();
+            }
+            write_atomic(&path, version.as_bytes()).unwrap();
+        }
+        assert_eq!(backups(&path).len(), BACKUPS);
+        assert_eq!(parse(&path).unwrap(), 4);
+
+        // A file that's merely wrong is left for the user to fix
+        fs::write(&path, "four").unwrap();
+        assert!(load_or_restore(&path, parse, truncated).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "four");
+
+        // A truncated file and a bad newest backup: the next one wins
+        fs::write(&path, "").unwrap();
+        fs::write(backup_path(&path, 1), "garbage").unwrap();
+        assert_eq!(load_or_restore(&path, parse, truncated).unwrap(), 2);
+        assert_eq!(parse(&path).unwrap(), 2);
+
+        let corrupt = fs::read_dir(&dir)
+            .unwrap()
+            .filter(|e| {
+                e.as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .contains(".corrupt-")
+            })
+            .count();
+        assert_eq!(corrupt, 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
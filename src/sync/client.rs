@@ -11,6 +11,8 @@ use color_eyre::{Result, eyre::eyre};
 use reqwest::{Client, header};
 use tracing::debug;
 
+use crate::http::{self, ClientSettings};
+
 use super::types::{
     AuthToken, StreamContents, StreamItemIds, Subscription, SubscriptionList, Tag, TagList,
     UnreadCount, UserInfo, streams,
@@ -32,14 +34,23 @@ impl GReaderClient {
     ///
     /// * `base_url` - The API base URL (e.g., "https://freshrss.example.com/api/greader.php")
     pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_settings(base_url, &ClientSettings::default())
+            .expect("Failed to create HTTP client")
+    }
+
+    /// Create a new client with the given proxy and TLS settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The API base URL
+    /// * `settings` - Proxy and TLS settings, usually [`ClientSettings::global`]
+    pub fn with_settings(base_url: impl Into<String>, settings: &ClientSettings) -> Result<Self> {
         let base_url = base_url.into().trim_end_matches('/').to_string();
-        let client = Client::builder()
-            .user_agent(concat!("feedo/", env!("CARGO_PKG_VERSION")))
+        let client = http::client_builder(settings)?
             .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .expect("Failed to create HTTP client");
+            .build()?;
 
-        Self { base_url, client }
+        Ok(Self { base_url, client })
     }
 
     /// Login and get an auth token.
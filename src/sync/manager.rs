@@ -10,7 +10,8 @@ use color_eyre::Result;
 use tracing::{debug, info};
 
 use crate::config::{Config, FeedConfig, FolderConfig};
-use crate::feed::FeedCache;
+use crate::feed::{FeedCache, Retention};
+use crate::http::ClientSettings;
 use crate::sync::{AuthToken, GReaderClient, StreamOptions};
 
 /// Result of a sync operation.
@@ -24,10 +25,21 @@ pub struct SyncResult {
     pub items_marked_read: usize,
     /// Number of items marked as read on server (from local).
     pub items_synced_to_server: usize,
+    /// Items read on the server but not locally, as (feed URL, item ID) pairs.
+    pub read_on_server: Vec<(String, String)>,
     /// Errors encountered (non-fatal).
     pub errors: Vec<String>,
 }
 
+impl SyncResult {
+    /// Mark the items that were read on the server as read in the local cache.
+    pub fn apply_read_states(&self, cache: &mut FeedCache) {
+        for (feed_url, item_id) in &self.read_on_server {
+            cache.set_item_read(feed_url, item_id, true);
+        }
+    }
+}
+
 /// Sync manager for bidirectional sync.
 pub struct SyncManager {
     client: GReaderClient,
@@ -36,8 +48,13 @@ pub struct SyncManager {
 
 impl SyncManager {
     /// Create a new sync manager.
-    pub async fn connect(server: &str, username: &str, password: &str) -> Result<Self> {
-        let client = GReaderClient::new(server);
+    pub async fn connect(
+        server: &str,
+        username: &str,
+        password: &str,
+        settings: &ClientSettings,
+    ) -> Result<Self> {
+        let client = GReaderClient::with_settings(server, settings)?;
         let auth = client.login(username, password).await?;
         Ok(Self { client, auth })
     }
@@ -118,6 +135,7 @@ impl SyncManager {
                         name,
                         url,
                         sync_id: Some(sync_id),
+                        ..Default::default()
                     });
                     result.feeds_imported += 1;
                 }
@@ -130,6 +148,7 @@ impl SyncManager {
                             name,
                             url,
                             sync_id: Some(sync_id),
+                            ..Default::default()
                         }
                     })
                     .collect();
@@ -138,6 +157,7 @@ impl SyncManager {
                     name: category,
                     icon: None,
                     expanded: true,
+                    retention: Retention::default(),
                     feeds: new_feeds,
                 });
             }
@@ -149,6 +169,7 @@ impl SyncManager {
                 name,
                 url,
                 sync_id: Some(sync_id),
+                ..Default::default()
             });
             result.feeds_imported += 1;
         }
@@ -162,6 +183,16 @@ impl SyncManager {
 
     /// Sync read states from server to local cache.
     pub async fn sync_read_states_from_server(&self, cache: &mut FeedCache) -> Result<SyncResult> {
+        let result = self.fetch_read_states_from_server(cache).await?;
+        result.apply_read_states(cache);
+        Ok(result)
+    }
+
+    /// Find items that are read on the server but unread locally.
+    ///
+    /// The cache is left untouched; the items are returned in
+    /// [`SyncResult::read_on_server`].
+    pub async fn fetch_read_states_from_server(&self, cache: &FeedCache) -> Result<SyncResult> {
         let mut result = SyncResult::default();
 
         // Get subscriptions to map feed IDs to URLs
@@ -192,20 +223,17 @@ impl SyncManager {
                     .any(|c| c.contains("/state/com.google/read"));
 
                 if is_read_on_server {
-                    // Mark as read locally using link+title to generate ID
-                    if let Some(link) = item.link() {
-                        let local_id = crate::feed::CachedItem::generate_id(
-                            Some(link),
-                            item.title.as_deref().unwrap_or(""),
-                        );
-                        // Check if already read locally
-                        let already_read = cache
+                    // Match the local item by link; server item IDs are the server's own
+                    let local_item = item.link().and_then(|link| {
+                        cache
                             .get(&sub.url)
-                            .and_then(|f| f.items.iter().find(|i| i.id == local_id))
-                            .map_or(false, |i| i.read);
-
-                        if !already_read {
-                            cache.set_item_read(&sub.url, &local_id, true);
+                            .and_then(|f| f.items.iter().find(|i| i.link.as_deref() == Some(link)))
+                    });
+                    if let Some(local_item) = local_item {
+                        if !local_item.read {
+                            result
+                                .read_on_server
+                                .push((sub.url.clone(), local_item.id.clone()));
                             result.items_marked_read += 1;
                         }
                     }
@@ -213,10 +241,7 @@ impl SyncManager {
             }
         }
 
-        info!(
-            "Marked {} items as read from server",
-            result.items_marked_read
-        );
+        info!("Found {} items read on server", result.items_marked_read);
         Ok(result)
     }
 
@@ -275,14 +300,13 @@ impl SyncManager {
                     continue; // Already read on server
                 }
 
-                // Check if read locally
+                // Check if read locally, matching by link
                 if let Some(link) = server_item.link() {
-                    let local_id = crate::feed::CachedItem::generate_id(
-                        Some(link),
-                        server_item.title.as_deref().unwrap_or(""),
-                    );
-
-                    if let Some(local_item) = cached_feed.items.iter().find(|i| i.id == local_id) {
+                    if let Some(local_item) = cached_feed
+                        .items
+                        .iter()
+                        .find(|i| i.link.as_deref() == Some(link))
+                    {
                         if local_item.read {
                             to_mark_read.push(server_item.id.clone());
                         }
@@ -320,6 +344,22 @@ impl SyncManager {
         &self,
         config: &mut Config,
         cache: &mut FeedCache,
+    ) -> Result<SyncResult> {
+        let result = self.full_sync_remote(config, cache).await?;
+        result.apply_read_states(cache);
+        Ok(result)
+    }
+
+    /// Run the network side of a full sync without modifying the cache.
+    ///
+    /// Subscriptions are imported into `config`. Items read on the server are
+    /// returned in [`SyncResult::read_on_server`] for the caller to apply with
+    /// [`SyncResult::apply_read_states`], so the cache can stay in use elsewhere
+    /// while the sync runs.
+    pub async fn full_sync_remote(
+        &self,
+        config: &mut Config,
+        cache: &FeedCache,
     ) -> Result<SyncResult> {
         let mut result = SyncResult::default();
 
@@ -330,10 +370,11 @@ impl SyncManager {
         result.feeds_existing = import_result.feeds_existing;
         result.errors.extend(import_result.errors);
 
-        // 2. Sync read states from server to local
+        // 2. Collect read states from server
         info!("Step 2: Syncing read states from server...");
-        let from_server = self.sync_read_states_from_server(cache).await?;
+        let from_server = self.fetch_read_states_from_server(cache).await?;
         result.items_marked_read = from_server.items_marked_read;
+        result.read_on_server = from_server.read_on_server;
         result.errors.extend(from_server.errors);
 
         // 3. Sync local read states to server
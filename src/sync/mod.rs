@@ -12,6 +12,7 @@
 //! # Example
 //!
 //! ```ignore
+//! use feedo::http::ClientSettings;
 //! use feedo::sync::{GReaderClient, SyncManager};
 //!
 //! // Low-level client
@@ -20,7 +21,7 @@
 //! let subs = client.subscriptions(&auth).await?;
 //!
 //! // High-level sync manager
-//! let manager = SyncManager::connect(server, user, pass).await?;
+//! let manager = SyncManager::connect(server, user, pass, &ClientSettings::global(&config)).await?;
 //! let result = manager.full_sync(&mut config, &mut cache).await?;
 //! ```
 
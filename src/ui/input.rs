@@ -1,10 +1,15 @@
 //! Input handling.
 
+use chrono::Utc;
 use crossterm::event::KeyCode;
+use reqwest::Url;
+
+use color_eyre::Result;
 
 use crate::app::App;
 use crate::config::FeedConfig;
-use crate::feed::FeedDiscovery;
+use crate::feed::DiscoveredFeed;
+use crate::http::{ClientSettings, RequestOptions};
 
 /// Result of handling a key press.
 pub enum KeyResult {
@@ -16,7 +21,7 @@ pub enum KeyResult {
 
 impl App {
     /// Handle a key press event.
-    pub async fn handle_key(&mut self, key: KeyCode) -> KeyResult {
+    pub fn handle_key(&mut self, key: KeyCode) -> KeyResult {
         // Clear transient messages
         self.ui.clear_error();
         self.ui.clear_status();
@@ -24,18 +29,22 @@ impl App {
         match self.ui.mode {
             super::Mode::Search => self.handle_search_key(key),
             super::Mode::ThemePicker => self.handle_theme_picker_key(key),
-            super::Mode::AddFeedUrl => self.handle_add_feed_url_key(key).await,
+            super::Mode::AddFeedUrl => self.handle_add_feed_url_key(key),
             super::Mode::AddFeedSelect => self.handle_add_feed_select_key(key),
             super::Mode::AddFeedName => self.handle_add_feed_name_key(key),
-            super::Mode::AddFeedFolder => self.handle_add_feed_folder_key(key).await,
-            super::Mode::ConfirmDelete => self.handle_confirm_delete_key(key).await,
+            super::Mode::AddFeedFolder => self.handle_add_feed_folder_key(key),
+            super::Mode::ConfirmDelete => self.handle_confirm_delete_key(key),
             super::Mode::ErrorDialog => self.handle_error_dialog_key(key),
             super::Mode::About => self.handle_about_key(key),
             super::Mode::Share => self.handle_share_key(key),
             super::Mode::Syncing | super::Mode::Updating => KeyResult::Continue, // Ignore input
             super::Mode::Help => self.handle_help_key(key),
             super::Mode::UpdateConfirm => self.handle_update_confirm_key(key),
-            super::Mode::Normal => self.handle_normal_key(key).await,
+            super::Mode::FeedHealth => self.handle_feed_health_key(key),
+            super::Mode::Downloads => self.handle_downloads_key(key),
+            super::Mode::FeedInfo => self.handle_feed_info_key(key),
+            super::Mode::Archive => self.handle_archive_key(key),
+            super::Mode::Normal => self.handle_normal_key(key),
         }
     }
 
@@ -67,27 +76,23 @@ impl App {
                 self.ui.search_query.push(c);
                 self.perform_search();
             }
-            KeyCode::Down | KeyCode::Tab => {
-                if !self.ui.search_results.is_empty() {
-                    self.ui.search_selected =
-                        (self.ui.search_selected + 1) % self.ui.search_results.len();
-                }
+            KeyCode::Down | KeyCode::Tab if !self.ui.search_results.is_empty() => {
+                self.ui.search_selected =
+                    (self.ui.search_selected + 1) % self.ui.search_results.len();
             }
-            KeyCode::Up | KeyCode::BackTab => {
-                if !self.ui.search_results.is_empty() {
-                    self.ui.search_selected = self
-                        .ui
-                        .search_selected
-                        .checked_sub(1)
-                        .unwrap_or(self.ui.search_results.len() - 1);
-                }
+            KeyCode::Up | KeyCode::BackTab if !self.ui.search_results.is_empty() => {
+                self.ui.search_selected = self
+                    .ui
+                    .search_selected
+                    .checked_sub(1)
+                    .unwrap_or(self.ui.search_results.len() - 1);
             }
             _ => {}
         }
         KeyResult::Continue
     }
 
-    async fn handle_normal_key(&mut self, key: KeyCode) -> KeyResult {
+    fn handle_normal_key(&mut self, key: KeyCode) -> KeyResult {
         match key {
             // Quit (only 'q' - Ctrl+c is handled by the terminal)
             KeyCode::Char('q') => return KeyResult::Quit,
@@ -112,8 +117,13 @@ impl App {
 
             // Add feed
             KeyCode::Char('n') => {
-                self.ui.reset_add_feed();
-                self.ui.mode = super::Mode::AddFeedUrl;
+                if self.ui.syncing {
+                    self.ui
+                        .set_error("Wait for the sync to finish before adding feeds");
+                } else {
+                    self.ui.reset_add_feed();
+                    self.ui.mode = super::Mode::AddFeedUrl;
+                }
             }
 
             // Navigation
@@ -127,31 +137,61 @@ impl App {
 
             // Actions
             KeyCode::Char('r') => {
-                self.ui.set_status("Refreshing feeds...");
-                self.feeds.refresh_all().await;
-                self.ui.set_status("Feeds refreshed!");
+                if self.is_refreshing() {
+                    self.ui.set_status("Refresh already in progress");
+                } else {
+                    self.start_refresh_all();
+                }
             }
             KeyCode::Char('o') => self.open_link(),
             KeyCode::Char('s') => self.open_share_dialog(),
+            KeyCode::Char('H') => {
+                self.ui.health_index = 0;
+                self.ui.mode = super::Mode::FeedHealth;
+            }
+            KeyCode::Char('c') if self.previous_body().is_some() => {
+                self.ui.show_diff = !self.ui.show_diff;
+                self.ui.scroll_offset = 0;
+            }
+            KeyCode::Char('D') => self.download_enclosures(),
+            KeyCode::Char('p') => self.play_enclosure(),
+            KeyCode::Char('i')
+                if self.ui.panel == super::Panel::Feeds && self.selected_list_feed().is_some() =>
+            {
+                self.ui.mode = super::Mode::FeedInfo;
+            }
+            KeyCode::Char('z') if self.ui.selected_feed.is_some() => {
+                self.ui.archive_index = 0;
+                self.ui.archive_preview = false;
+                self.ui.mode = super::Mode::Archive;
+            }
+            KeyCode::Char('L') => {
+                self.ui.download_index = self.downloads.downloads().len().saturating_sub(1);
+                self.ui.mode = super::Mode::Downloads;
+            }
             KeyCode::Char('S') => {
                 if self.ui.sync_enabled && !self.ui.syncing {
-                    self.ui.syncing = true;
-                    self.ui.set_status("⟳ Syncing...");
-                    match self.run_sync().await {
-                        Ok(()) => {}
-                        Err(e) => self.ui.set_error(format!("Sync failed: {e}")),
+                    if let Err(e) = self.start_sync() {
+                        self.ui.set_error(format!("Sync failed: {e}"));
                     }
-                    self.ui.syncing = false;
                 } else if !self.ui.sync_enabled {
                     self.ui
                         .set_error("No sync configured. Run 'feedo sync login' first.");
                 }
             }
             KeyCode::Char(' ') => self.toggle_read(),
+            KeyCode::Char('*') => self.toggle_star(),
             KeyCode::Char('a') => self.mark_all_read(),
 
             // Delete feed
-            KeyCode::Char('d') | KeyCode::Delete => self.delete_selected_feed(),
+            KeyCode::Char('d') | KeyCode::Delete => {
+                if self.ui.syncing {
+                    self.ui
+                        .set_error("Wait for the sync to finish before deleting feeds");
+                } else {
+                    self.delete_selected_feed();
+                }
+            }
 
             // About dialog
             KeyCode::Char('A') => {
@@ -164,10 +204,8 @@ impl App {
             }
 
             // Update (if available)
-            KeyCode::Char('U') => {
-                if self.ui.update_available.is_some() {
-                    self.ui.mode = super::Mode::UpdateConfirm;
-                }
+            KeyCode::Char('U') if self.ui.update_available.is_some() => {
+                self.ui.mode = super::Mode::UpdateConfirm;
             }
 
             _ => {}
@@ -217,16 +255,14 @@ impl App {
         KeyResult::Continue
     }
 
-    async fn handle_add_feed_url_key(&mut self, key: KeyCode) -> KeyResult {
+    fn handle_add_feed_url_key(&mut self, key: KeyCode) -> KeyResult {
         match key {
             KeyCode::Esc => {
                 self.ui.reset_add_feed();
                 self.ui.mode = super::Mode::Normal;
             }
-            KeyCode::Enter => {
-                if !self.ui.add_feed_url.is_empty() {
-                    self.discover_feeds().await;
-                }
+            KeyCode::Enter if !self.ui.add_feed_url.is_empty() && !self.ui.discovering => {
+                self.discover_feeds();
             }
             KeyCode::Backspace => {
                 self.ui.add_feed_url.pop();
@@ -252,20 +288,16 @@ impl App {
                 }
                 self.ui.mode = super::Mode::AddFeedName;
             }
-            KeyCode::Char('j') | KeyCode::Down => {
-                if !self.ui.discovered_feeds.is_empty() {
-                    self.ui.discovered_feed_index =
-                        (self.ui.discovered_feed_index + 1) % self.ui.discovered_feeds.len();
-                }
+            KeyCode::Char('j') | KeyCode::Down if !self.ui.discovered_feeds.is_empty() => {
+                self.ui.discovered_feed_index =
+                    (self.ui.discovered_feed_index + 1) % self.ui.discovered_feeds.len();
             }
-            KeyCode::Char('k') | KeyCode::Up => {
-                if !self.ui.discovered_feeds.is_empty() {
-                    self.ui.discovered_feed_index = self
-                        .ui
-                        .discovered_feed_index
-                        .checked_sub(1)
-                        .unwrap_or(self.ui.discovered_feeds.len() - 1);
-                }
+            KeyCode::Char('k') | KeyCode::Up if !self.ui.discovered_feeds.is_empty() => {
+                self.ui.discovered_feed_index = self
+                    .ui
+                    .discovered_feed_index
+                    .checked_sub(1)
+                    .unwrap_or(self.ui.discovered_feeds.len() - 1);
             }
             _ => {}
         }
@@ -294,7 +326,7 @@ impl App {
     }
 
     /// Handle keys in folder selection mode.
-    async fn handle_add_feed_folder_key(&mut self, key: KeyCode) -> KeyResult {
+    fn handle_add_feed_folder_key(&mut self, key: KeyCode) -> KeyResult {
         let folder_count = self.config.folders.len();
         // Options: None (root), Some(0..folder_count-1) for existing folders, or "new folder"
         // We represent this as: 0 = root, 1..=folder_count = existing folders, folder_count+1 = new folder
@@ -307,22 +339,21 @@ impl App {
                     self.ui.creating_new_folder = false;
                     self.ui.add_feed_new_folder.clear();
                 }
-                KeyCode::Enter => {
-                    if !self.ui.add_feed_new_folder.is_empty() {
-                        // Create the folder and select it
-                        let new_folder = crate::config::FolderConfig {
-                            name: self.ui.add_feed_new_folder.clone(),
-                            icon: Some("📁".to_string()),
-                            expanded: true,
-                            feeds: vec![],
-                        };
-                        self.config.folders.push(new_folder);
-                        self.ui.add_feed_folder_index = Some(self.config.folders.len() - 1);
-                        self.ui.creating_new_folder = false;
-                        self.ui.add_feed_new_folder.clear();
-                        // Now add the feed
-                        self.add_discovered_feed().await;
-                    }
+                KeyCode::Enter if !self.ui.add_feed_new_folder.is_empty() => {
+                    // Create the folder and select it
+                    let new_folder = crate::config::FolderConfig {
+                        name: self.ui.add_feed_new_folder.clone(),
+                        icon: Some("📁".to_string()),
+                        expanded: true,
+                        retention: crate::feed::Retention::default(),
+                        feeds: vec![],
+                    };
+                    self.config.folders.push(new_folder);
+                    self.ui.add_feed_folder_index = Some(self.config.folders.len() - 1);
+                    self.ui.creating_new_folder = false;
+                    self.ui.add_feed_new_folder.clear();
+                    // Now add the feed
+                    self.add_discovered_feed();
                 }
                 KeyCode::Backspace => {
                     self.ui.add_feed_new_folder.pop();
@@ -378,7 +409,7 @@ impl App {
                         self.ui.add_feed_new_folder.clear();
                     } else {
                         // Add the feed to selected folder (or root)
-                        self.add_discovered_feed().await;
+                        self.add_discovered_feed();
                     }
                 }
                 _ => {}
@@ -387,49 +418,48 @@ impl App {
         KeyResult::Continue
     }
 
-    /// Discover feeds from the entered URL.
-    async fn discover_feeds(&mut self) {
+    /// Start discovering feeds from the entered URL in the background.
+    fn discover_feeds(&mut self) {
         self.ui.discovering = true;
+        self.spawn_discovery(self.ui.add_feed_url.clone());
+    }
 
-        match FeedDiscovery::new() {
-            Ok(discovery) => {
-                match discovery.discover(&self.ui.add_feed_url).await {
-                    Ok(feeds) => {
-                        self.ui.discovered_feeds = feeds;
-                        self.ui.discovered_feed_index = 0;
-
-                        if self.ui.discovered_feeds.len() == 1 {
-                            // Only one feed found, go straight to name input
-                            if let Some(feed) = self.ui.discovered_feeds.first() {
-                                self.ui.add_feed_name = feed.title.clone().unwrap_or_default();
-                            }
-                            self.ui.mode = super::Mode::AddFeedName;
-                        } else {
-                            // Multiple feeds, let user choose
-                            self.ui.mode = super::Mode::AddFeedSelect;
-                        }
-                    }
-                    Err(e) => {
-                        self.ui.show_error_dialog(
-                            format!("No feeds found at this URL: {e}"),
-                            Some(format!("URL: {}", self.ui.add_feed_url)),
-                        );
+    /// Handle the result of a background feed discovery.
+    pub(crate) fn finish_discovery(&mut self, url: &str, result: Result<Vec<DiscoveredFeed>>) {
+        // Ignore stale results if the user cancelled or changed the URL meanwhile
+        if self.ui.mode != super::Mode::AddFeedUrl || url != self.ui.add_feed_url {
+            return;
+        }
+
+        self.ui.discovering = false;
+
+        match result {
+            Ok(feeds) => {
+                self.ui.discovered_feeds = feeds;
+                self.ui.discovered_feed_index = 0;
+
+                if self.ui.discovered_feeds.len() == 1 {
+                    // Only one feed found, go straight to name input
+                    if let Some(feed) = self.ui.discovered_feeds.first() {
+                        self.ui.add_feed_name = feed.title.clone().unwrap_or_default();
                     }
+                    self.ui.mode = super::Mode::AddFeedName;
+                } else {
+                    // Multiple feeds, let user choose
+                    self.ui.mode = super::Mode::AddFeedSelect;
                 }
             }
             Err(e) => {
                 self.ui.show_error_dialog(
-                    format!("Failed to discover feeds: {e}"),
-                    Some(format!("URL: {}", self.ui.add_feed_url)),
+                    format!("No feeds found at this URL: {e}"),
+                    Some(format!("URL: {url}")),
                 );
             }
         }
-
-        self.ui.discovering = false;
     }
 
     /// Add the selected discovered feed.
-    async fn add_discovered_feed(&mut self) {
+    fn add_discovered_feed(&mut self) {
         let Some(discovered) = self.ui.discovered_feeds.get(self.ui.discovered_feed_index) else {
             self.ui.set_error("No feed selected");
             return;
@@ -445,6 +475,10 @@ impl App {
         };
 
         let url = discovered.url.clone();
+        if self.config.has_feed(&url) {
+            self.ui.set_error(format!("Already subscribed to {url}"));
+            return;
+        }
 
         // Get folder name for sync category
         let folder_name = self.ui.add_feed_folder_index.and_then(|idx| {
@@ -455,12 +489,25 @@ impl App {
             }
         });
 
-        let feed_config = FeedConfig {
+        let mut feed_config = FeedConfig {
             name: name.clone(),
             url: url.clone(),
             sync_id: None, // Will be populated on next sync
+            ..Default::default()
         };
 
+        // Credentials typed into the URL go to encrypted storage, and only
+        // for feeds on the same site
+        let (entered, options) = RequestOptions::from_url_userinfo(&self.ui.add_feed_url);
+        if let Some((username, password)) = options.basic_auth
+            && let (Ok(entered), Ok(feed_url)) = (Url::parse(&entered), Url::parse(&url))
+            && crate::http::same_origin(&entered, &feed_url)
+            && !feed_config.set_credentials(&username, &password)
+        {
+            self.ui
+                .set_error("Could not encrypt credentials; stored in config file");
+        }
+
         // Add to folder if one is selected, otherwise add to root feeds
         match self.ui.add_feed_folder_index {
             Some(folder_idx) if folder_idx != usize::MAX => {
@@ -488,12 +535,15 @@ impl App {
             if let Some(sync) = &self.config.sync {
                 if let Some((username, password)) = sync.get_credentials() {
                     let server = sync.server.clone();
+                    let settings = ClientSettings::global(&self.config);
                     let feed_url = url.clone();
                     let feed_title = name.clone();
                     let category = folder_name.map(|f| format!("user/-/label/{}", f));
                     tokio::spawn(async move {
-                        if let Ok(manager) =
-                            crate::sync::SyncManager::connect(&server, &username, &password).await
+                        if let Ok(manager) = crate::sync::SyncManager::connect(
+                            &server, &username, &password, &settings,
+                        )
+                        .await
                         {
                             let _ = manager
                                 .client()
@@ -511,19 +561,12 @@ impl App {
         }
 
         // Reload feed manager from config to sync folder structure
-        match crate::feed::FeedManager::new(&self.config) {
-            Ok(new_feeds) => {
-                self.feeds = new_feeds;
-            }
-            Err(e) => {
-                self.ui.set_error(format!("Failed to reload feeds: {e}"));
-                return;
-            }
-        }
+        self.feeds.reload(&self.config);
 
-        // Refresh the newly added feed
-        let feed_idx = self.feeds.feeds.len().saturating_sub(1);
-        self.feeds.refresh_feed(feed_idx).await;
+        // Refresh the newly added feed in the background
+        if let Some(feed_idx) = self.feeds.feeds.iter().position(|f| f.url == url) {
+            self.start_refresh([feed_idx]);
+        }
 
         // Update UI
         self.rebuild_feed_list();
@@ -555,10 +598,10 @@ impl App {
     }
 
     /// Handle keys in delete confirmation mode.
-    async fn handle_confirm_delete_key(&mut self, key: KeyCode) -> KeyResult {
+    fn handle_confirm_delete_key(&mut self, key: KeyCode) -> KeyResult {
         match key {
             KeyCode::Char('y' | 'Y') => {
-                self.perform_delete().await;
+                self.perform_delete();
             }
             KeyCode::Char('n' | 'N') | KeyCode::Esc => {
                 self.ui.reset_delete();
@@ -569,6 +612,183 @@ impl App {
         KeyResult::Continue
     }
 
+    /// Handle keys in the feed health view.
+    fn handle_feed_health_key(&mut self, key: KeyCode) -> KeyResult {
+        let broken = self.feeds.broken_feeds(Utc::now());
+        self.ui.health_index = self.ui.health_index.min(broken.len().saturating_sub(1));
+        let selected = broken.get(self.ui.health_index).map(|(idx, _)| *idx);
+
+        match key {
+            KeyCode::Esc | KeyCode::Char('q' | 'H') => {
+                self.ui.mode = super::Mode::Normal;
+            }
+            KeyCode::Char('j') | KeyCode::Down if !broken.is_empty() => {
+                self.ui.health_index = (self.ui.health_index + 1) % broken.len();
+            }
+            KeyCode::Char('k') | KeyCode::Up if !broken.is_empty() => {
+                self.ui.health_index = self
+                    .ui
+                    .health_index
+                    .checked_sub(1)
+                    .unwrap_or(broken.len() - 1);
+            }
+            KeyCode::Enter => {
+                if let Some(feed_idx) = selected {
+                    self.select_feed(feed_idx);
+                    self.ui.mode = super::Mode::Normal;
+                }
+            }
+            KeyCode::Char('r') => {
+                if let Some(feed_idx) = selected {
+                    self.start_refresh([feed_idx]);
+                    self.ui
+                        .set_status(format!("Retrying {}", self.feeds.feeds[feed_idx].name));
+                }
+            }
+            KeyCode::Char('d') | KeyCode::Delete => {
+                if self.ui.syncing {
+                    self.ui
+                        .set_error("Wait for the sync to finish before deleting feeds");
+                } else if let Some(feed_idx) = selected {
+                    self.select_feed(feed_idx);
+                    self.ui.pending_delete_feed = Some(feed_idx);
+                    self.ui.mode = super::Mode::ConfirmDelete;
+                }
+            }
+            _ => {}
+        }
+        KeyResult::Continue
+    }
+
+    /// Handle keys in the feed info view.
+    fn handle_feed_info_key(&mut self, key: KeyCode) -> KeyResult {
+        match key {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q' | 'i') => {
+                self.ui.mode = super::Mode::Normal;
+            }
+            KeyCode::Char('o') => self.open_website(),
+            _ => {}
+        }
+        KeyResult::Continue
+    }
+
+    /// Handle keys in the archive view.
+    fn handle_archive_key(&mut self, key: KeyCode) -> KeyResult {
+        use super::state::ARCHIVE_PAGE_SIZE;
+
+        let Some(feed_idx) = self.ui.selected_feed else {
+            self.ui.mode = super::Mode::Normal;
+            return KeyResult::Continue;
+        };
+        let len = self.feeds.feeds[feed_idx].archive.len();
+        let last = len.saturating_sub(1);
+        let index = self.ui.archive_index.min(last);
+
+        if self.ui.archive_preview {
+            match key {
+                KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q' | 'h') | KeyCode::Left => {
+                    self.ui.archive_preview = false;
+                }
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.ui.archive_scroll = self.ui.archive_scroll.saturating_add(1);
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.ui.archive_scroll = self.ui.archive_scroll.saturating_sub(1);
+                }
+                KeyCode::Char('o') => self.open_archived_link(feed_idx, index),
+                _ => {}
+            }
+            return KeyResult::Continue;
+        }
+
+        match key {
+            KeyCode::Esc | KeyCode::Char('q' | 'z') => {
+                self.ui.mode = super::Mode::Normal;
+            }
+            KeyCode::Char('j') | KeyCode::Down => self.ui.archive_index = (index + 1).min(last),
+            KeyCode::Char('k') | KeyCode::Up => self.ui.archive_index = index.saturating_sub(1),
+            KeyCode::Char('n' | 'l') | KeyCode::Right | KeyCode::PageDown => {
+                self.ui.archive_index =
+                    ((index / ARCHIVE_PAGE_SIZE + 1) * ARCHIVE_PAGE_SIZE).min(last);
+            }
+            KeyCode::Char('p' | 'h') | KeyCode::Left | KeyCode::PageUp => {
+                self.ui.archive_index =
+                    (index / ARCHIVE_PAGE_SIZE).saturating_sub(1) * ARCHIVE_PAGE_SIZE;
+            }
+            KeyCode::Enter if len > 0 => {
+                self.ui.archive_preview = true;
+                self.ui.archive_scroll = 0;
+            }
+            KeyCode::Char('o') => self.open_archived_link(feed_idx, index),
+            KeyCode::Char('u') => {
+                // Back to the article list as unread
+                let feed = &mut self.feeds.feeds[feed_idx];
+                if index < feed.archive.len() {
+                    let mut item = feed.archive.remove(index);
+                    item.mark_unread();
+                    self.feeds.cache.set_item_read(&feed.url, &item.id, false);
+                    self.ui
+                        .set_status(format!("Moved \"{}\" back to unread", item.title));
+                    feed.items.push(item);
+                }
+            }
+            _ => {}
+        }
+        KeyResult::Continue
+    }
+
+    /// Open the link of an archived article.
+    fn open_archived_link(&mut self, feed_idx: usize, index: usize) {
+        let Some(link) = self.feeds.feeds[feed_idx]
+            .archive
+            .get(index)
+            .and_then(|item| item.link.clone())
+        else {
+            return;
+        };
+        if open::that(&link).is_err() {
+            self.ui.show_error_dialog(
+                "Failed to open browser",
+                Some(format!("Could not open the article.\n\n{link}")),
+            );
+        }
+    }
+
+    /// Handle keys in the downloads view.
+    fn handle_downloads_key(&mut self, key: KeyCode) -> KeyResult {
+        let count = self.downloads.downloads().len();
+        self.ui.download_index = self.ui.download_index.min(count.saturating_sub(1));
+        let index = self.ui.download_index;
+
+        match key {
+            KeyCode::Esc | KeyCode::Char('q' | 'L') => {
+                self.ui.mode = super::Mode::Normal;
+            }
+            KeyCode::Char('j') | KeyCode::Down if count > 0 => {
+                self.ui.download_index = (index + 1) % count;
+            }
+            KeyCode::Char('k') | KeyCode::Up if count > 0 => {
+                self.ui.download_index = index.checked_sub(1).unwrap_or(count - 1);
+            }
+            KeyCode::Char('c') => self.downloads.cancel(index),
+            KeyCode::Char('r') => self.downloads.resume(index),
+            KeyCode::Char('p') | KeyCode::Enter => {
+                if let Some(download) = self.downloads.downloads().get(index) {
+                    let target = if download.path.is_file() {
+                        download.path.to_string_lossy().into_owned()
+                    } else {
+                        download.url.clone()
+                    };
+                    if let Err(e) = crate::download::play(&self.config.player, &target) {
+                        self.ui.set_error(e.to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+        KeyResult::Continue
+    }
+
     /// Handle keys in error dialog mode.
     fn handle_error_dialog_key(&mut self, key: KeyCode) -> KeyResult {
         match key {
@@ -632,10 +852,10 @@ impl App {
     }
 
     /// Actually delete the feed or folder after confirmation.
-    async fn perform_delete(&mut self) {
+    fn perform_delete(&mut self) {
         // Check if we're deleting a folder
         if let Some(folder_idx) = self.ui.pending_delete_folder {
-            self.perform_delete_folder(folder_idx).await;
+            self.perform_delete_folder(folder_idx);
             return;
         }
 
@@ -671,11 +891,13 @@ impl App {
                     // Use sync_id if available, otherwise skip server delete
                     if let Some(feed_id) = sync_id.clone() {
                         let server = sync.server.clone();
+                        let settings = ClientSettings::global(&self.config);
                         // Spawn in background - don't block UI
                         tokio::spawn(async move {
-                            if let Ok(manager) =
-                                crate::sync::SyncManager::connect(&server, &username, &password)
-                                    .await
+                            if let Ok(manager) = crate::sync::SyncManager::connect(
+                                &server, &username, &password, &settings,
+                            )
+                            .await
                             {
                                 let _ = manager
                                     .client()
@@ -713,9 +935,7 @@ impl App {
         }
 
         // Reload feed manager from config (simplest way to keep indices consistent)
-        if let Ok(new_feeds) = crate::feed::FeedManager::new(&self.config) {
-            self.feeds = new_feeds;
-        }
+        self.feeds.reload(&self.config);
 
         self.rebuild_feed_list();
         self.select_first_feed();
@@ -726,7 +946,7 @@ impl App {
 
     /// Delete a folder and all its feeds.
     #[allow(clippy::map_unwrap_or)]
-    async fn perform_delete_folder(&mut self, folder_idx: usize) {
+    fn perform_delete_folder(&mut self, folder_idx: usize) {
         // Get the folder info including sync_ids
         let (folder_name, feed_sync_ids): (String, Vec<String>) = self
             .config
@@ -754,10 +974,13 @@ impl App {
             if let Some(sync) = &self.config.sync {
                 if let Some((username, password)) = sync.get_credentials() {
                     let server = sync.server.clone();
+                    let settings = ClientSettings::global(&self.config);
                     // Spawn in background - don't block UI
                     tokio::spawn(async move {
-                        if let Ok(manager) =
-                            crate::sync::SyncManager::connect(&server, &username, &password).await
+                        if let Ok(manager) = crate::sync::SyncManager::connect(
+                            &server, &username, &password, &settings,
+                        )
+                        .await
                         {
                             for sync_id in &feed_sync_ids {
                                 let _ = manager
@@ -785,9 +1008,7 @@ impl App {
         }
 
         // Reload feed manager from config
-        if let Ok(new_feeds) = crate::feed::FeedManager::new(&self.config) {
-            self.feeds = new_feeds;
-        }
+        self.feeds.reload(&self.config);
 
         self.rebuild_feed_list();
         self.select_first_feed();
@@ -906,6 +1127,7 @@ impl App {
                 // Mark item as read when opening
                 self.mark_current_read();
                 self.ui.show_content = true;
+                self.ui.show_diff = false;
                 self.ui.panel = super::Panel::Content;
                 self.ui.scroll_offset = 0;
             }
@@ -925,7 +1147,40 @@ impl App {
         }
     }
 
+    /// The feed under the cursor in the feeds panel, if it is a feed rather than a folder.
+    fn selected_list_feed(&self) -> Option<usize> {
+        match self.ui.feed_list.get(self.ui.feed_list_index) {
+            Some(super::FeedListItem::Feed(idx)) => Some(*idx),
+            _ => None,
+        }
+    }
+
+    /// Open the selected feed's website in the browser.
+    fn open_website(&mut self) {
+        let Some(site) = self
+            .ui
+            .selected_feed
+            .and_then(|idx| self.feeds.feeds.get(idx))
+            .and_then(|feed| feed.site_url())
+        else {
+            self.ui.set_error("This feed doesn't link to a website");
+            return;
+        };
+
+        if open::that(&site).is_err() {
+            self.ui.show_error_dialog(
+                "Failed to open browser",
+                Some(format!("Could not open the feed's website.\n\n{site}")),
+            );
+        }
+    }
+
     fn open_link(&mut self) {
+        if self.ui.panel == super::Panel::Feeds && self.selected_list_feed().is_some() {
+            self.open_website();
+            return;
+        }
+
         if let Some(item) = self.selected_item() {
             if let Some(link) = &item.link {
                 // Check if we can actually open a browser (need display on Linux)
@@ -966,6 +1221,71 @@ impl App {
         }
     }
 
+    /// Queue the enclosures of the selected article for download.
+    fn download_enclosures(&mut self) {
+        let Some(feed_idx) = self.ui.selected_feed else {
+            return;
+        };
+        let Some(item) = self.selected_item().cloned() else {
+            return;
+        };
+        if item.enclosures.is_empty() {
+            self.ui.set_error("This article has no attachments");
+            return;
+        }
+
+        let feed_name = self.feeds.feeds[feed_idx].name.clone();
+        let mut queued = 0;
+        for enclosure in &item.enclosures {
+            match self.downloads.enqueue(&feed_name, &item.title, enclosure) {
+                Ok(true) => queued += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    self.ui.set_error(e.to_string());
+                    return;
+                }
+            }
+        }
+
+        if queued == 0 {
+            self.ui.set_status("Already downloaded");
+        } else {
+            self.ui
+                .set_status(format!("⬇ Downloading {queued} file(s)"));
+        }
+    }
+
+    /// Play the first enclosure of the selected article.
+    ///
+    /// Uses the downloaded file if there is one, and streams the URL otherwise.
+    fn play_enclosure(&mut self) {
+        let Some(feed_idx) = self.ui.selected_feed else {
+            return;
+        };
+        let Some(enclosure) = self
+            .selected_item()
+            .and_then(|item| item.enclosures.first())
+            .cloned()
+        else {
+            self.ui.set_error("This article has no attachments");
+            return;
+        };
+
+        let target = self
+            .downloads
+            .local_file(&self.feeds.feeds[feed_idx].name, &enclosure)
+            .map_or(enclosure.url, |path| path.to_string_lossy().into_owned());
+
+        match crate::download::play(&self.config.player, &target) {
+            Ok(()) => {
+                self.mark_current_read();
+                self.ui
+                    .set_status(format!("▶ Playing with {}", self.config.player));
+            }
+            Err(e) => self.ui.set_error(e.to_string()),
+        }
+    }
+
     fn toggle_read(&mut self) {
         if matches!(self.ui.panel, super::Panel::Items | super::Panel::Content) {
             if let Some(feed_idx) = self.ui.selected_feed {
@@ -976,7 +1296,11 @@ impl App {
                         let feed_url = feed.url.clone();
                         let item_id = item.id.clone();
                         let is_read = item.read;
+                        let changed = item.changed;
                         self.feeds.cache.set_item_read(&feed_url, &item_id, is_read);
+                        self.feeds
+                            .cache
+                            .set_item_changed(&feed_url, &item_id, changed);
                         let _ = self.feeds.cache.save();
                     }
                 }
@@ -984,15 +1308,42 @@ impl App {
         }
     }
 
+    fn toggle_star(&mut self) {
+        if !matches!(self.ui.panel, super::Panel::Items | super::Panel::Content) {
+            return;
+        }
+        let Some(feed) = self
+            .ui
+            .selected_feed
+            .and_then(|idx| self.feeds.feeds.get_mut(idx))
+        else {
+            return;
+        };
+        if let Some(item) = feed.items.get_mut(self.ui.selected_item) {
+            item.starred = !item.starred;
+            self.feeds
+                .cache
+                .set_item_starred(&feed.url, &item.id, item.starred);
+            let _ = self.feeds.cache.save();
+        }
+    }
+
     fn mark_current_read(&mut self) {
         if let Some(feed_idx) = self.ui.selected_feed {
             if let Some(feed) = self.feeds.feeds.get_mut(feed_idx) {
                 if let Some(item) = feed.items.get_mut(self.ui.selected_item) {
+                    // Keep the old text around while the article is on screen
+                    if let Some(body) = item.previous_body.clone() {
+                        self.ui.seen_change = Some((item.id.clone(), body));
+                    }
                     item.mark_read();
                     // Persist to cache
                     let feed_url = feed.url.clone();
                     let item_id = item.id.clone();
                     self.feeds.cache.set_item_read(&feed_url, &item_id, true);
+                    self.feeds
+                        .cache
+                        .set_item_changed(&feed_url, &item_id, false);
                     let _ = self.feeds.cache.save();
                 }
             }
@@ -1035,13 +1386,7 @@ impl App {
 
         for (feed_idx, feed) in self.feeds.feeds.iter().enumerate() {
             for (item_idx, item) in feed.items.iter().enumerate() {
-                let matches = item.title.to_lowercase().contains(&query)
-                    || item
-                        .summary
-                        .as_ref()
-                        .is_some_and(|s| s.to_lowercase().contains(&query));
-
-                if matches {
+                if item.matches(&query) {
                     self.ui.search_results.push((feed_idx, item_idx));
                 }
             }
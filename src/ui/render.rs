@@ -1,13 +1,16 @@
 //! UI rendering.
 
+use chrono::Utc;
 use ratatui::{
     prelude::*,
-    widgets::{Block, BorderType, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
 };
 
 use super::state::FeedListItem;
 use super::{Mode, Panel};
 use crate::app::App;
+use crate::download::{Download, DownloadState};
+use crate::feed::{DiffSpan, HealthStatus, word_diff};
 
 /// Modern ASCII art logo for Feedo - a cute RSS-eating dog.
 pub const LOGO: &str = r"
@@ -92,6 +95,22 @@ impl App {
             self.render_help_dialog(frame, area);
         }
 
+        if self.ui.mode == Mode::FeedHealth {
+            self.render_feed_health(frame, area);
+        }
+
+        if self.ui.mode == Mode::Downloads {
+            self.render_downloads(frame, area);
+        }
+
+        if self.ui.mode == Mode::FeedInfo {
+            self.render_feed_info(frame, area);
+        }
+
+        if self.ui.mode == Mode::Archive {
+            self.render_archive(frame, area);
+        }
+
         // Update confirmation dialog
         if self.ui.mode == Mode::UpdateConfirm {
             self.render_update_confirm_dialog(frame, area);
@@ -214,12 +233,20 @@ impl App {
                             .any(|f| f.feed_indices.contains(idx));
                         let indent = if in_folder { "    " } else { "" };
 
-                        let text = if unread > 0 {
+                        let mut text = if unread > 0 {
                             format!("{indent}● {} ({unread})", feed.name)
                         } else {
                             format!("{indent}○ {}", feed.name)
                         };
 
+                        // Flag feeds that are failing to fetch
+                        if let Some(health) = self.feeds.health(*idx, Utc::now())
+                            && health.status != HealthStatus::Healthy
+                        {
+                            text.push(' ');
+                            text.push_str(health.status.icon());
+                        }
+
                         // Apply selection style only for non-ListState approach
                         let style = if is_selected {
                             Style::default().fg(accent).bold()
@@ -247,7 +274,10 @@ impl App {
                     .borders(Borders::ALL)
                     .border_style(border_style)
                     .border_type(BorderType::Rounded)
-                    .title(" 📡 Feeds "),
+                    .title(self.ui.profile.as_ref().map_or_else(
+                        || " 📡 Feeds ".to_string(),
+                        |name| format!(" 📡 Feeds ({name}) "),
+                    )),
             )
             .highlight_symbol("▶ ");
 
@@ -266,7 +296,13 @@ impl App {
             .enumerate()
             .map(|(i, item)| {
                 let is_selected = i == self.ui.selected_item;
-                let prefix = if item.read { "○" } else { "●" };
+                // Read state and star get a column each, so neither hides the other
+                let state = match (item.read, item.changed) {
+                    (_, true) => "✎",
+                    (true, false) => "○",
+                    (false, false) => "●",
+                };
+                let star = if item.starred { "★" } else { " " };
 
                 let style = if is_selected {
                     Style::default().fg(accent).bold()
@@ -277,7 +313,7 @@ impl App {
                 };
 
                 // Truncate title to fit (use chars() for Unicode safety)
-                let max_width = area.width.saturating_sub(6) as usize;
+                let max_width = area.width.saturating_sub(8) as usize;
                 let title: String = if item.title.chars().count() > max_width {
                     item.title
                         .chars()
@@ -288,7 +324,7 @@ impl App {
                     item.title.clone()
                 };
 
-                ListItem::new(format!(" {prefix} {title}")).style(style)
+                ListItem::new(format!(" {state} {star} {title}")).style(style)
             })
             .collect();
 
@@ -330,13 +366,61 @@ impl App {
             |item| {
                 let mut text = format!("  {}\n\n", item.title);
 
+                // Metadata header
+                let fmt = |d: chrono::DateTime<chrono::Utc>| d.format("%Y-%m-%d %H:%M");
                 if let Some(date) = item.published {
-                    let _ = write!(text, "  📅 {}\n\n", date.format("%Y-%m-%d %H:%M"));
+                    let _ = write!(text, "  📅 {}", fmt(date));
+                    if let Some(updated) = item.updated.filter(|&u| u > date) {
+                        let _ = write!(text, "  (updated {})", fmt(updated));
+                    }
+                    text.push('\n');
+                }
+                if !item.authors.is_empty() {
+                    let _ = writeln!(text, "  ✍ {}", item.authors.join(", "));
+                }
+                if !item.categories.is_empty() {
+                    let _ = writeln!(text, "  🏷 {}", item.categories.join(", "));
+                }
+                let feed_name = self
+                    .ui
+                    .selected_feed
+                    .and_then(|idx| self.feeds.feeds.get(idx))
+                    .map_or("", |f| f.name.as_str());
+                for enclosure in &item.enclosures {
+                    let _ = write!(text, "  {} {}", enclosure.icon(), enclosure.file_name());
+                    let mut details = Vec::new();
+                    if let Some(mime) = &enclosure.mime_type {
+                        details.push(mime.clone());
+                    }
+                    if let Some(length) = enclosure.length {
+                        details.push(format_size(length));
+                    }
+                    if let Some(duration) = enclosure.duration {
+                        details.push(format_duration(duration));
+                    }
+                    if !details.is_empty() {
+                        let _ = write!(text, " ({})", details.join(", "));
+                    }
+                    if let Some(download) = self.downloads.get(&enclosure.url) {
+                        let _ = write!(text, " │ {}", download_status(download));
+                    } else if self.downloads.local_file(feed_name, enclosure).is_some() {
+                        text.push_str(" │ ✓ downloaded");
+                    }
+                    text.push('\n');
+                }
+                if !item.enclosures.is_empty() {
+                    text.push_str("  D download │ p play\n");
+                }
+                if self.previous_body().is_some() {
+                    text.push_str("  ✎ Updated since you read it │ c show changes\n");
+                }
+                if !text.ends_with("\n\n") {
+                    text.push('\n');
                 }
 
-                if let Some(summary) = &item.summary {
+                if let Some(body) = item.body() {
                     // Strip HTML tags
-                    let clean = strip_html(summary);
+                    let clean = strip_html(body);
                     text.push_str("  ");
                     text.push_str(&clean.replace('\n', "\n  "));
                 }
@@ -344,6 +428,9 @@ impl App {
                 if let Some(link) = &item.link {
                     let _ = write!(text, "\n\n  🔗 {link}");
                 }
+                for link in &item.alternate_links {
+                    let _ = write!(text, "\n  ↗ {link}");
+                }
 
                 text
             },
@@ -355,13 +442,22 @@ impl App {
             Style::default().fg(muted)
         };
 
+        let diff = self
+            .selected_item()
+            .filter(|_| self.ui.show_diff)
+            .and_then(|item| Some((self.previous_body()?, item.body()?)));
+        let (content, title) = match diff {
+            Some((old, new)) => (self.diff_text(old, new), " ✎ Changes "),
+            None => (Text::from(content), " 📖 Content "),
+        };
+
         let paragraph = Paragraph::new(content)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
                     .border_style(border_style)
                     .border_type(BorderType::Rounded)
-                    .title(" 📖 Content "),
+                    .title(title),
             )
             .wrap(Wrap { trim: false })
             .scroll((self.ui.scroll_offset, 0));
@@ -369,6 +465,30 @@ impl App {
         frame.render_widget(paragraph, area);
     }
 
+    /// Word diff between two versions of an article, removals struck through.
+    fn diff_text(&self, old: &str, new: &str) -> Text<'static> {
+        let palette = self.theme.palette();
+        let mut spans = vec![Span::raw("  ")];
+        for span in word_diff(&strip_html(old), &strip_html(new)) {
+            let (text, style) = match span {
+                DiffSpan::Same(t) => (t, Style::default()),
+                DiffSpan::Added(t) => (t, Style::default().fg(palette.success).bold()),
+                DiffSpan::Removed(t) => (t, Style::default().fg(palette.error).crossed_out()),
+            };
+            spans.push(Span::styled(text, style));
+            spans.push(Span::raw(" "));
+        }
+
+        Text::from(vec![
+            Line::from(Span::styled(
+                "  Changes since you read it (c to go back)",
+                Style::default().fg(palette.muted).italic(),
+            )),
+            Line::from(""),
+            Line::from(spans),
+        ])
+    }
+
     #[allow(clippy::option_if_let_else)]
     fn render_status_bar(&self, frame: &mut Frame, area: Rect) {
         let muted = self.theme.palette().muted;
@@ -379,7 +499,11 @@ impl App {
         let sync_indicator: Vec<Span> = if self.ui.syncing {
             vec![Span::styled(" ⟳ syncing │ ", Style::default().fg(muted))]
         } else if self.ui.refreshing {
-            vec![Span::styled(" ⟳ refreshing │ ", Style::default().fg(muted))]
+            let text = self.ui.refresh_progress.map_or_else(
+                || " ⟳ refreshing │ ".to_string(),
+                |progress| format!(" ⟳ refreshing {progress} │ "),
+            );
+            vec![Span::styled(text, Style::default().fg(muted))]
         } else if self.ui.sync_enabled {
             vec![Span::styled(" ☁ │ ", Style::default().fg(muted))]
         } else {
@@ -401,6 +525,12 @@ impl App {
             let text_style = Style::default().fg(muted);
 
             let mut spans = sync_indicator.clone();
+            if self.ui.read_only {
+                spans.push(Span::styled("🔒 read-only │ ", text_style));
+            }
+            if let Some(info) = self.refresh_schedule_text() {
+                spans.push(Span::styled(info, text_style));
+            }
             spans.extend(vec![
                 Span::styled("n", key_style),
                 Span::styled(": add  ", text_style),
@@ -424,6 +554,369 @@ impl App {
         frame.render_widget(bar, area);
     }
 
+    /// Describe the last and next refresh times, e.g. "↻ 14:32 → 15:02 │ ".
+    fn refresh_schedule_text(&self) -> Option<String> {
+        if self.ui.refreshing {
+            return None;
+        }
+
+        let fmt =
+            |t: chrono::DateTime<chrono::Utc>| t.with_timezone(&chrono::Local).format("%H:%M");
+
+        match (self.ui.last_refresh, self.ui.next_refresh) {
+            (Some(last), Some(next)) => Some(format!("↻ {} → {} │ ", fmt(last), fmt(next))),
+            (Some(last), None) => Some(format!("↻ {} │ ", fmt(last))),
+            (None, Some(next)) => Some(format!("↻ next {} │ ", fmt(next))),
+            (None, None) => None,
+        }
+    }
+
+    fn render_feed_health(&self, frame: &mut Frame, area: Rect) {
+        use std::fmt::Write;
+
+        let accent = self.theme.palette().accent;
+        let muted = self.theme.palette().muted;
+        let fg = self.theme.palette().fg;
+        let popup_area = centered_rect(75, 70, area);
+        frame.render_widget(Clear, popup_area);
+
+        let fmt = |t: chrono::DateTime<Utc>| {
+            t.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        };
+
+        let broken = self.feeds.broken_feeds(Utc::now());
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(accent))
+            .border_type(BorderType::Rounded)
+            .title(format!(" 🩺 Feed Health ({} broken) ", broken.len()))
+            .title_bottom(
+                Line::from(" ↑↓ navigate │ ↵ go to feed │ r retry │ d delete │ Esc close ")
+                    .centered(),
+            );
+
+        if broken.is_empty() {
+            let text = Paragraph::new("\n  ✓ All feeds are healthy")
+                .style(Style::default().fg(self.theme.palette().success))
+                .block(block);
+            frame.render_widget(text, popup_area);
+            return;
+        }
+
+        let items: Vec<ListItem> = broken
+            .iter()
+            .enumerate()
+            .map(|(i, (feed_idx, health))| {
+                let selected = i == self.ui.health_index;
+                let name = &self.feeds.feeds[*feed_idx].name;
+                let (label, color) = match health.status {
+                    HealthStatus::Dead => ("suspended", self.theme.palette().error),
+                    _ => ("failing", self.theme.palette().warning),
+                };
+
+                let mut details = format!("{} failures", health.failures);
+                if let Some(since) = health.failing_since {
+                    let _ = write!(details, " since {}", fmt(since));
+                }
+                if let Some(status) = health.http_status {
+                    let _ = write!(details, " │ HTTP {status}");
+                }
+                let _ = write!(
+                    details,
+                    " │ last success {}",
+                    health.last_success.map_or_else(|| "never".to_string(), fmt)
+                );
+
+                let name_style = if selected {
+                    Style::default().fg(accent).bold()
+                } else {
+                    Style::default().fg(fg)
+                };
+
+                ListItem::new(vec![
+                    Line::from(vec![
+                        Span::raw(if selected { " ▸ " } else { "   " }),
+                        Span::raw(format!("{} ", health.status.icon())),
+                        Span::styled(name.clone(), name_style),
+                        Span::styled(format!("  [{label}]"), Style::default().fg(color)),
+                    ]),
+                    Line::from(Span::styled(
+                        format!("     {details}"),
+                        Style::default().fg(muted),
+                    )),
+                    Line::from(Span::styled(
+                        format!("     {}", health.error.as_deref().unwrap_or_default()),
+                        Style::default().fg(muted).italic(),
+                    )),
+                ])
+            })
+            .collect();
+
+        let mut state = ListState::default().with_selected(Some(self.ui.health_index));
+        frame.render_stateful_widget(List::new(items).block(block), popup_area, &mut state);
+    }
+
+    fn render_feed_info(&self, frame: &mut Frame, area: Rect) {
+        let accent = self.theme.palette().accent;
+        let muted = self.theme.palette().muted;
+        let fg = self.theme.palette().fg;
+        let popup_area = centered_rect(70, 60, area);
+        frame.render_widget(Clear, popup_area);
+
+        let Some(feed) = self
+            .ui
+            .selected_feed
+            .and_then(|idx| self.feeds.feeds.get(idx))
+        else {
+            return;
+        };
+        let meta = &feed.meta;
+        let fmt = |t: chrono::DateTime<Utc>| {
+            t.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        };
+
+        let label_style = Style::default().fg(muted);
+        let value_style = Style::default().fg(fg);
+        let row = |label: &str, value: String| {
+            Line::from(vec![
+                Span::styled(format!("  {label:<12}"), label_style),
+                Span::styled(value, value_style),
+            ])
+        };
+
+        let mut lines = vec![
+            Line::from(Span::styled(
+                format!("  {}", meta.title.as_deref().unwrap_or(&feed.name)),
+                Style::default().fg(accent).bold(),
+            )),
+            Line::from(""),
+        ];
+        if let Some(description) = &meta.description {
+            lines.push(Line::from(Span::styled(
+                format!("  {}", strip_html(description).trim()),
+                value_style.italic(),
+            )));
+            lines.push(Line::from(""));
+        }
+
+        lines.push(row("Feed", feed.url.clone()));
+        if let Some(site) = feed.site_url() {
+            lines.push(row("Website", site));
+        }
+        if let Some(language) = &meta.language {
+            lines.push(row("Language", language.clone()));
+        }
+        if !meta.authors.is_empty() {
+            lines.push(row("Authors", meta.authors.join(", ")));
+        }
+        if let Some(generator) = &meta.generator {
+            lines.push(row("Generator", generator.clone()));
+        }
+        if let Some(icon) = &meta.icon {
+            lines.push(row("Icon", icon.clone()));
+        }
+        if let Some(built) = meta.last_build_date {
+            lines.push(row("Last build", fmt(built)));
+        }
+        if let Some(fetched) = feed.last_updated {
+            lines.push(row("Fetched", fmt(fetched)));
+        }
+        lines.push(row(
+            "Articles",
+            format!("{} ({} unread)", feed.items.len(), feed.unread_count()),
+        ));
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(accent))
+            .border_type(BorderType::Rounded)
+            .title(format!(" ℹ {} ", feed.name))
+            .title_bottom(Line::from(" o open website │ Esc close ").centered());
+
+        let paragraph = Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false });
+        frame.render_widget(paragraph, popup_area);
+    }
+
+    fn render_archive(&self, frame: &mut Frame, area: Rect) {
+        use super::state::ARCHIVE_PAGE_SIZE;
+
+        let accent = self.theme.palette().accent;
+        let muted = self.theme.palette().muted;
+        let fg = self.theme.palette().fg;
+        let popup_area = centered_rect(80, 80, area);
+        frame.render_widget(Clear, popup_area);
+
+        let Some(feed) = self
+            .ui
+            .selected_feed
+            .and_then(|idx| self.feeds.feeds.get(idx))
+        else {
+            return;
+        };
+        let archive = &feed.archive;
+        let index = self.ui.archive_index.min(archive.len().saturating_sub(1));
+        let fmt = |item: &crate::feed::FeedItem| {
+            item.published.map_or_else(
+                || "          ".to_string(),
+                |d| d.format("%Y-%m-%d").to_string(),
+            )
+        };
+
+        if self.ui.archive_preview
+            && let Some(item) = archive.get(index)
+        {
+            let mut text = format!("  {}\n  📅 {}\n\n", item.title, fmt(item).trim());
+            if let Some(body) = item.body() {
+                text.push_str("  ");
+                text.push_str(&strip_html(body).replace('\n', "\n  "));
+            }
+            if let Some(link) = &item.link {
+                text.push_str("\n\n  🔗 ");
+                text.push_str(link);
+            }
+
+            let paragraph = Paragraph::new(text)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(accent))
+                        .border_type(BorderType::Rounded)
+                        .title(" 🗄 Archived article ")
+                        .title_bottom(
+                            Line::from(" ↑↓ scroll │ o open in browser │ Esc back ").centered(),
+                        ),
+                )
+                .wrap(Wrap { trim: false })
+                .scroll((self.ui.archive_scroll, 0));
+            frame.render_widget(paragraph, popup_area);
+            return;
+        }
+
+        let pages = archive.len().div_ceil(ARCHIVE_PAGE_SIZE).max(1);
+        let page = index / ARCHIVE_PAGE_SIZE;
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(accent))
+            .border_type(BorderType::Rounded)
+            .title(format!(" 🗄 Archive: {} ({}) ", feed.name, archive.len()))
+            .title_bottom(
+                Line::from(format!(
+                    " page {}/{pages} │ ↑↓ navigate │ ←→ page │ ↵ read │ u unread │ o open │ Esc close ",
+                    page + 1
+                ))
+                .centered(),
+            );
+
+        if archive.is_empty() {
+            let text = Paragraph::new(
+                "\n  Nothing archived yet. Read articles that drop out of the feed end up here.",
+            )
+            .style(Style::default().fg(muted))
+            .block(block);
+            frame.render_widget(text, popup_area);
+            return;
+        }
+
+        let start = page * ARCHIVE_PAGE_SIZE;
+        let items: Vec<ListItem> = archive
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(ARCHIVE_PAGE_SIZE)
+            .map(|(i, item)| {
+                let selected = i == index;
+                let title_style = if selected {
+                    Style::default().fg(accent).bold()
+                } else {
+                    Style::default().fg(fg)
+                };
+                ListItem::new(Line::from(vec![
+                    Span::raw(if selected { " ▸ " } else { "   " }),
+                    Span::styled(fmt(item), Style::default().fg(muted)),
+                    Span::raw("  "),
+                    Span::styled(item.title.clone(), title_style),
+                ]))
+            })
+            .collect();
+
+        let mut state = ListState::default().with_selected(Some(index - start));
+        frame.render_stateful_widget(List::new(items).block(block), popup_area, &mut state);
+    }
+
+    fn render_downloads(&self, frame: &mut Frame, area: Rect) {
+        let accent = self.theme.palette().accent;
+        let muted = self.theme.palette().muted;
+        let fg = self.theme.palette().fg;
+        let popup_area = centered_rect(75, 70, area);
+        frame.render_widget(Clear, popup_area);
+
+        let downloads = self.downloads.downloads();
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(accent))
+            .border_type(BorderType::Rounded)
+            .title(format!(
+                " ⬇ Downloads ({} active) ",
+                self.downloads.active_count()
+            ))
+            .title_bottom(
+                Line::from(" ↑↓ navigate │ p play │ c cancel │ r resume │ Esc close ").centered(),
+            );
+
+        if downloads.is_empty() {
+            let text = Paragraph::new(
+                "\n  Nothing downloaded yet. Press D on an article with attachments.",
+            )
+            .style(Style::default().fg(muted))
+            .block(block);
+            frame.render_widget(text, popup_area);
+            return;
+        }
+
+        let items: Vec<ListItem> = downloads
+            .iter()
+            .enumerate()
+            .map(|(i, download)| {
+                let selected = i == self.ui.download_index;
+                let color = match download.state {
+                    DownloadState::Done => self.theme.palette().success,
+                    DownloadState::Failed(_) => self.theme.palette().error,
+                    DownloadState::Cancelled => self.theme.palette().warning,
+                    _ => muted,
+                };
+                let name_style = if selected {
+                    Style::default().fg(accent).bold()
+                } else {
+                    Style::default().fg(fg)
+                };
+
+                ListItem::new(vec![
+                    Line::from(vec![
+                        Span::raw(if selected { " ▸ " } else { "   " }),
+                        Span::styled(download.title.clone(), name_style),
+                    ]),
+                    Line::from(Span::styled(
+                        format!("     {}", download_status(download)),
+                        Style::default().fg(color),
+                    )),
+                    Line::from(Span::styled(
+                        format!("     {}", download.path.display()),
+                        Style::default().fg(muted).italic(),
+                    )),
+                ])
+            })
+            .collect();
+
+        let mut state = ListState::default().with_selected(Some(self.ui.download_index));
+        frame.render_stateful_widget(List::new(items).block(block), popup_area, &mut state);
+    }
+
     fn render_search_overlay(&self, frame: &mut Frame, area: Rect) {
         let accent = self.theme.palette().accent;
         let popup_area = centered_rect(60, 50, area);
@@ -627,12 +1120,27 @@ impl App {
                             Style::default()
                         };
 
-                        ListItem::new(format!(
+                        let mut text = format!(
                             "  {prefix} {title} ({feed_type})\n      {url}",
                             feed_type = feed.feed_type,
                             url = feed.url
-                        ))
-                        .style(style)
+                        );
+                        if let Some(description) = &feed.meta.description {
+                            let description: String = strip_html(description)
+                                .split_whitespace()
+                                .collect::<Vec<_>>()
+                                .join(" ");
+                            let short: String = description.chars().take(70).collect();
+                            let ellipsis = if short.len() < description.len() {
+                                "…"
+                            } else {
+                                ""
+                            };
+                            text.push_str("\n      ");
+                            text.push_str(&short);
+                            text.push_str(ellipsis);
+                        }
+                        ListItem::new(text).style(style)
                     })
                     .collect();
 
@@ -1171,6 +1679,27 @@ impl App {
                 Span::raw("    "),
                 Span::styled("Refresh all", desc_style),
             ]),
+            Line::from(vec![
+                Span::styled("  [", bracket_style),
+                Span::styled("H", key_style),
+                Span::styled("]", bracket_style),
+                Span::raw("    "),
+                Span::styled("Feed health", desc_style),
+            ]),
+            Line::from(vec![
+                Span::styled("  [", bracket_style),
+                Span::styled("z", key_style),
+                Span::styled("]", bracket_style),
+                Span::raw("    "),
+                Span::styled("Article archive", desc_style),
+            ]),
+            Line::from(vec![
+                Span::styled("  [", bracket_style),
+                Span::styled("i", key_style),
+                Span::styled("]", bracket_style),
+                Span::raw("    "),
+                Span::styled("Feed info", desc_style),
+            ]),
         ];
 
         let left_para = Paragraph::new(left_lines);
@@ -1191,6 +1720,13 @@ impl App {
                 Span::raw(""),
                 Span::styled("Toggle read", desc_style),
             ]),
+            Line::from(vec![
+                Span::styled("  [", bracket_style),
+                Span::styled("*", key_style),
+                Span::styled("]", bracket_style),
+                Span::raw("    "),
+                Span::styled("Star (kept by retention)", desc_style),
+            ]),
             Line::from(vec![
                 Span::styled("  [", bracket_style),
                 Span::styled("a", key_style),
@@ -1212,6 +1748,34 @@ impl App {
                 Span::raw("    "),
                 Span::styled("Share article", desc_style),
             ]),
+            Line::from(vec![
+                Span::styled("  [", bracket_style),
+                Span::styled("D", key_style),
+                Span::styled("]", bracket_style),
+                Span::raw("    "),
+                Span::styled("Download attachments", desc_style),
+            ]),
+            Line::from(vec![
+                Span::styled("  [", bracket_style),
+                Span::styled("c", key_style),
+                Span::styled("]", bracket_style),
+                Span::raw("    "),
+                Span::styled("Show article changes", desc_style),
+            ]),
+            Line::from(vec![
+                Span::styled("  [", bracket_style),
+                Span::styled("p", key_style),
+                Span::styled("]", bracket_style),
+                Span::raw("    "),
+                Span::styled("Play attachment", desc_style),
+            ]),
+            Line::from(vec![
+                Span::styled("  [", bracket_style),
+                Span::styled("L", key_style),
+                Span::styled("]", bracket_style),
+                Span::raw("    "),
+                Span::styled("Downloads", desc_style),
+            ]),
             Line::from(""),
             // Search & Sync section
             Line::from(vec![
@@ -1356,6 +1920,65 @@ impl App {
     }
 }
 
+/// Describe a download's state and progress, e.g. "⬇  42% ▕████░░░░▏ 5.0 MB / 12.0 MB".
+fn download_status(download: &Download) -> String {
+    let size = match download.total {
+        Some(total) => format!(
+            "{} / {}",
+            format_size(download.downloaded),
+            format_size(total)
+        ),
+        None => format_size(download.downloaded),
+    };
+
+    match &download.state {
+        DownloadState::Queued => "⏳ queued".to_string(),
+        DownloadState::Running => match download.progress() {
+            Some(progress) => {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let filled = (progress * 20.0).round() as usize;
+                format!(
+                    "⬇ {:>3.0}% ▕{}{}▏ {size}",
+                    progress * 100.0,
+                    "█".repeat(filled),
+                    "░".repeat(20 - filled)
+                )
+            }
+            None => format!("⬇ {size}"),
+        },
+        DownloadState::Done => "✓ downloaded".to_string(),
+        DownloadState::Failed(e) => format!("✗ {e} ({size})"),
+        DownloadState::Cancelled => format!("⏸ cancelled ({size})"),
+    }
+}
+
+/// Format a byte count, e.g. "12.3 MB".
+#[allow(clippy::cast_precision_loss)]
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+/// Format a duration in seconds, e.g. "1:02:03" or "4:05".
+fn format_duration(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{m}:{s:02}")
+    }
+}
+
 /// Create a centered rectangle.
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
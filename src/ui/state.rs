@@ -1,8 +1,13 @@
 //! UI state management.
 
-use crate::feed::DiscoveredFeed;
+use chrono::{DateTime, Utc};
+
+use crate::feed::{DiscoveredFeed, RefreshProgress};
 use ratatui::widgets::ListState;
 
+/// Number of articles per page in the archive view.
+pub const ARCHIVE_PAGE_SIZE: usize = 20;
+
 /// Active panel in the UI.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Panel {
@@ -49,6 +54,14 @@ pub enum Mode {
     UpdateConfirm,
     /// Update in progress.
     Updating,
+    /// Feed health view.
+    FeedHealth,
+    /// Download queue view.
+    Downloads,
+    /// Feed metadata view.
+    FeedInfo,
+    /// Archived articles of a feed.
+    Archive,
 }
 
 /// Item in the feed list (can be folder or feed).
@@ -94,6 +107,13 @@ pub struct UiState {
     /// Content scroll offset.
     pub scroll_offset: u16,
 
+    /// Whether the content panel shows what changed in an edited article.
+    pub show_diff: bool,
+
+    /// ID and earlier text of the last edited article opened, so its changes
+    /// can still be shown after opening it acknowledged them.
+    pub seen_change: Option<(String, String)>,
+
     /// Search query.
     pub search_query: String,
 
@@ -144,14 +164,40 @@ pub struct UiState {
     // --- Sync state ---
     /// Whether sync is configured.
     pub sync_enabled: bool,
+    /// Whether another instance is running, so changes aren't saved.
+    pub read_only: bool,
+    /// Name of the active profile, if not the default one.
+    pub profile: Option<String>,
     /// Last sync status message.
     pub sync_status: Option<String>,
     /// Whether sync is in progress.
     pub syncing: bool,
     /// Whether a sync should be triggered (deferred).
     pub pending_sync: bool,
-    /// Whether initial feed refresh is in progress.
+    /// Whether a feed refresh is in progress.
     pub refreshing: bool,
+    /// Progress of the running refresh (if any).
+    pub refresh_progress: Option<RefreshProgress>,
+    /// When feeds were last refreshed.
+    pub last_refresh: Option<DateTime<Utc>>,
+    /// When the next automatic refresh is due (None = manual only).
+    pub next_refresh: Option<DateTime<Utc>>,
+
+    // --- Feed health state ---
+    /// Selected row in the feed health view.
+    pub health_index: usize,
+
+    // --- Archive state ---
+    /// Selected article in the archive view.
+    pub archive_index: usize,
+    /// Whether the selected archived article is open for reading.
+    pub archive_preview: bool,
+    /// Scroll offset of the open archived article.
+    pub archive_scroll: u16,
+
+    // --- Downloads state ---
+    /// Selected row in the downloads view.
+    pub download_index: usize,
 
     // --- Delete confirmation state ---
     /// Feed index pending deletion (for confirmation).
@@ -239,6 +285,8 @@ impl Default for UiState {
             items_list_state: ListState::default(),
             show_content: false,
             scroll_offset: 0,
+            show_diff: false,
+            seen_change: None,
             search_query: String::new(),
             search_results: Vec::new(),
             search_selected: 0,
@@ -255,10 +303,20 @@ impl Default for UiState {
             creating_new_folder: false,
             share_platform_index: 0,
             sync_enabled: false,
+            read_only: false,
+            profile: None,
             sync_status: None,
             syncing: false,
             pending_sync: false,
             refreshing: false,
+            refresh_progress: None,
+            last_refresh: None,
+            next_refresh: None,
+            health_index: 0,
+            archive_index: 0,
+            archive_preview: false,
+            archive_scroll: 0,
+            download_index: 0,
             pending_delete_feed: None,
             pending_delete_folder: None,
             error_dialog: None,
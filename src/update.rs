@@ -2,6 +2,8 @@
 
 use std::process::Stdio;
 
+use crate::http::{self, ClientSettings};
+
 /// GitHub repository path for update checks.
 pub const GITHUB_REPO: &str = "ricardodantas/feedo";
 
@@ -92,18 +94,22 @@ pub fn detect_package_manager() -> PackageManager {
 }
 
 /// Check if a newer version is available on GitHub.
-pub async fn check_for_updates() -> VersionCheck {
-    check_for_updates_timeout(std::time::Duration::from_secs(5)).await
+pub async fn check_for_updates(settings: &ClientSettings) -> VersionCheck {
+    check_for_updates_timeout(std::time::Duration::from_secs(5), settings).await
 }
 
 /// Check if a newer version is available on GitHub with custom timeout.
-pub async fn check_for_updates_timeout(timeout: std::time::Duration) -> VersionCheck {
+pub async fn check_for_updates_timeout(
+    timeout: std::time::Duration,
+    settings: &ClientSettings,
+) -> VersionCheck {
     let url = format!("https://api.github.com/repos/{GITHUB_REPO}/releases/latest");
 
-    let client = match reqwest::Client::builder().timeout(timeout).build() {
-        Ok(c) => c,
-        Err(e) => return VersionCheck::CheckFailed(format!("Failed to create client: {e}")),
-    };
+    let client =
+        match http::client_builder(settings).and_then(|b| Ok(b.timeout(timeout).build()?)) {
+            Ok(c) => c,
+            Err(e) => return VersionCheck::CheckFailed(format!("Failed to create client: {e}")),
+        };
 
     let result = client
         .get(&url)
@@ -153,12 +159,11 @@ pub async fn check_for_updates_timeout(timeout: std::time::Duration) -> VersionC
 }
 
 /// Check for updates using crates.io API (no rate limits).
-pub async fn check_for_updates_crates_io() -> VersionCheck {
+pub async fn check_for_updates_crates_io(settings: &ClientSettings) -> VersionCheck {
     let url = "https://crates.io/api/v1/crates/feedo";
 
-    let client = match reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(5))
-        .build()
+    let client = match http::client_builder(settings)
+        .and_then(|b| Ok(b.timeout(std::time::Duration::from_secs(5)).build()?))
     {
         Ok(c) => c,
         Err(e) => return VersionCheck::CheckFailed(format!("Failed to create client: {e}")),
@@ -302,7 +307,11 @@ mod tests {
     #[tokio::test]
     async fn test_check_for_updates_does_not_panic() {
         // This actually hits the GitHub API, but with a short timeout
-        let result = check_for_updates_timeout(std::time::Duration::from_secs(5)).await;
+        let result = check_for_updates_timeout(
+            std::time::Duration::from_secs(5),
+            &ClientSettings::default(),
+        )
+        .await;
         // Should either succeed or fail gracefully, not panic
         match result {
             VersionCheck::UpdateAvailable { latest, current } => {